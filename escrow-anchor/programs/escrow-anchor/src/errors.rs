@@ -17,6 +17,27 @@ pub enum EscrowError {
     #[msg("Manager key provided is the current manager")]
     ManagerKeyAlreadySet,
 
+    #[msg("Manager key provided is not the pending manager")]
+    PendingManagerMismatch,
+
+    #[msg("No manager transfer is pending")]
+    NoPendingManager,
+
+    #[msg("Escrow state already uses the current layout")]
+    EscrowStateAlreadyMigrated,
+
+    #[msg("Making and updating offers is paused")]
+    MakePaused,
+
+    #[msg("Taking offers is paused")]
+    TakePaused,
+
+    #[msg("Mint provided is not permitted by the mint list")]
+    MintNotPermitted,
+
+    #[msg("Notes provided exceed the maximum length")]
+    NotesTooLong,
+
     #[msg("Offer key provided does not match expected")]
     OfferKeyMismatch,
 
@@ -25,4 +46,94 @@ pub enum EscrowError {
 
     #[msg("Argument provided resulted in overflow")]
     MathError,
+
+    #[msg("Fill amount provided is zero or exceeds the remaining offer amount")]
+    InvalidFillAmount,
+
+    #[msg("Expiry timestamp provided is not in the future")]
+    InvalidExpiry,
+
+    #[msg("Offer has expired")]
+    OfferExpired,
+
+    #[msg("Offer has not expired yet")]
+    OfferNotExpired,
+
+    #[msg("Taker key provided is not allowed to take this offer")]
+    TakerNotAllowed,
+
+    #[msg("Merkle proof provided does not match the offer's taker allowlist")]
+    InvalidMerkleProof,
+
+    #[msg("Token account required for a non-native mint was not provided")]
+    TokenAccountMissing,
+
+    #[msg("Withdraw amount provided would leave the offer without token A")]
+    InvalidUpdateAmount,
+
+    #[msg("Referrer key provided is the taker or is not registered")]
+    InvalidReferrer,
+
+    #[msg("Fee split recipients provided are empty, duplicated or do not sum to the maximum basis points")]
+    InvalidFeeSplit,
+
+    #[msg("Fee tiers provided exceed the maximum count")]
+    TooManyFeeTiers,
+
+    #[msg("Maker and taker must be different wallets")]
+    SelfTradeNotAllowed,
+
+    #[msg("Balance after transfer does not match the expected amount")]
+    BalanceInvariantViolated,
+
+    #[msg("Offer terms exceed the slippage limits provided by the taker")]
+    SlippageExceeded,
+
+    #[msg("Auction provided must end after it starts at a lower token B amount")]
+    InvalidAuction,
+
+    #[msg("Token A and token B mints provided are the same")]
+    IdenticalMints,
+
+    #[msg("Auction has ended")]
+    AuctionEnded,
+
+    #[msg("Auction has not ended yet")]
+    AuctionNotEnded,
+
+    #[msg("Bid provided is below the minimum bid or increment")]
+    BidTooLow,
+
+    #[msg("Bidder key provided does not match the highest bidder")]
+    BidderKeyMismatch,
+
+    #[msg("Auction maker cannot bid on its own auction")]
+    MakerCannotBid,
+
+    #[msg("Offer provided is on the other side of the book")]
+    OfferSideMismatch,
+
+    #[msg("Offers provided do not trade the same mint pair")]
+    OfferPairMismatch,
+
+    #[msg("Bid provided is below the ask")]
+    PricesDoNotCross,
+
+    #[msg("Basket legs provided are empty, exceed the maximum count, repeat a mint or are zero")]
+    InvalidBasket,
+
+    #[msg("Remaining account provided does not match the basket leg")]
+    BasketAccountMismatch,
+
+    #[msg("Bid refund account of the outbid bidder is missing")]
+    BidRefundMissing,
+
+    #[msg("Auction has already been settled")]
+    AuctionAlreadySettled,
+
+    #[msg("Fees must be distributed through the fee split once it is configured")]
+    FeeSplitConfigured,
+
+    #[msg("Offer amounts provided are zero or leave nothing to fill after the transfer fee")]
+    InvalidOfferAmount,
 }
@@ -0,0 +1,239 @@
+use crate::state::{
+    BasketLeg, FeeRoundingMode, FeeSplitRecipient, FeeTier, MintListMode, OfferKind, OfferSide,
+};
+use anchor_lang::prelude::*;
+
+#[event]
+pub struct OfferCreated {
+    pub offer: Pubkey,
+    pub id: u64,
+    pub maker: Pubkey,
+    pub token_mint_a: Pubkey,
+    pub token_mint_b: Pubkey,
+    /// Token A received by the vault net of any transfer fee, or token A wanted by a bid
+    pub token_a_offered_amount: u64,
+    /// Token B asked for, or token B received by the vault of a bid net of any transfer fee
+    pub token_b_wanted_amount: u64,
+    pub expires_at: Option<i64>,
+    pub kind: OfferKind,
+    pub side: OfferSide,
+}
+
+#[event]
+pub struct OfferUpdated {
+    pub offer: Pubkey,
+    pub maker: Pubkey,
+    pub token_a_offered_amount: u64,
+    pub token_b_wanted_amount: u64,
+}
+
+#[event]
+pub struct OfferTaken {
+    pub offer: Pubkey,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub referrer: Option<Pubkey>,
+    pub side: OfferSide,
+    pub token_a_amount: u64,
+    pub token_b_amount: u64,
+    /// Token A fee sent to the escrow fee account
+    pub token_a_fee_amount: u64,
+    /// Token B fee sent to the escrow fee account
+    pub token_b_fee_amount: u64,
+    /// Token A fee share paid to the referrer
+    pub token_a_referral_amount: u64,
+    /// Token B fee share paid to the referrer
+    pub token_b_referral_amount: u64,
+    pub token_a_remaining_amount: u64,
+}
+
+#[event]
+pub struct OfferCancelled {
+    pub offer: Pubkey,
+    pub maker: Pubkey,
+    pub side: OfferSide,
+    /// Token A of an ask or token B of a bid sent back to the maker, before any transfer fee
+    pub returned_amount: u64,
+    /// Whether the offer was reclaimed after expiring rather than cancelled by the maker
+    pub expired: bool,
+}
+
+#[event]
+pub struct OffersMatched {
+    pub ask: Pubkey,
+    pub bid: Pubkey,
+    pub cranker: Pubkey,
+    pub token_a_amount: u64,
+    /// Token B paid to the ask maker at the resting offer's price, before fees
+    pub token_b_amount: u64,
+    /// Token A fee sent to the escrow fee account
+    pub token_a_fee_amount: u64,
+    /// Token B fee sent to the escrow fee account, including the fee on the surplus
+    pub token_b_fee_amount: u64,
+    /// Token B released by the bid above the execution price, paid to the cranker less fees
+    pub surplus_amount: u64,
+}
+
+#[event]
+pub struct FeesUpdated {
+    /// Trading pair of a fee override, or `None` for the global fees. A closed override
+    /// reports the global fees its pair falls back to.
+    pub token_mint_a: Option<Pubkey>,
+    pub token_mint_b: Option<Pubkey>,
+    pub maker_fee_bps: u16,
+    pub taker_fee_bps: u16,
+}
+
+#[event]
+pub struct ManagerChanged {
+    pub previous_manager: Pubkey,
+    pub new_manager: Pubkey,
+}
+
+#[event]
+pub struct ManagerTransferProposed {
+    pub manager: Pubkey,
+    pub pending_manager: Pubkey,
+}
+
+#[event]
+pub struct ManagerTransferCancelled {
+    pub manager: Pubkey,
+    pub cancelled_manager: Pubkey,
+}
+
+#[event]
+pub struct PauseUpdated {
+    pub make_paused: bool,
+    pub take_paused: bool,
+}
+
+#[event]
+pub struct FeeTiersUpdated {
+    pub fee_tiers: Vec<FeeTier>,
+}
+
+#[event]
+pub struct TraderStatsCreated {
+    pub trader: Pubkey,
+}
+
+#[event]
+pub struct FeeRoundingModeUpdated {
+    pub fee_rounding_mode: FeeRoundingMode,
+}
+
+/// A closed fee split reports no recipients, fees are then collected by the manager.
+#[event]
+pub struct FeeSplitConfigUpdated {
+    pub recipients: Vec<FeeSplitRecipient>,
+}
+
+#[event]
+pub struct ReferralShareUpdated {
+    pub referral_share_bps: u16,
+}
+
+#[event]
+pub struct MintListModeUpdated {
+    pub mint_list_mode: MintListMode,
+}
+
+#[event]
+pub struct MintConfigUpdated {
+    pub mint: Pubkey,
+    pub allowed: bool,
+    pub denied: bool,
+    pub min_fee_amount: u64,
+}
+
+#[event]
+pub struct FeesCollected {
+    pub token_mint: Pubkey,
+    pub recipient_token_account: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct AuctionCreated {
+    pub auction: Pubkey,
+    pub id: u64,
+    pub maker: Pubkey,
+    pub token_mint_a: Pubkey,
+    pub token_mint_b: Pubkey,
+    /// Token A received by the vault, net of any transfer fee
+    pub token_a_amount: u64,
+    pub min_bid_amount: u64,
+    pub min_bid_increment: u64,
+    pub end_time: i64,
+}
+
+#[event]
+pub struct BidPlaced {
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    /// Token B received by the bid vault, net of any transfer fee
+    pub bid_amount: u64,
+    pub outbid_bidder: Option<Pubkey>,
+    /// Token B credited to the outbid bidder's refund, withdrawn with `withdraw_bid_refund`
+    pub outbid_amount: u64,
+}
+
+#[event]
+pub struct BidRefundWithdrawn {
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct AuctionSettled {
+    pub auction: Pubkey,
+    pub maker: Pubkey,
+    /// `None` when the auction ended without bids and token A went back to the maker
+    pub winner: Option<Pubkey>,
+    pub token_a_amount: u64,
+    pub token_b_amount: u64,
+    /// Token B fee sent to the escrow fee account
+    pub token_b_fee_amount: u64,
+}
+
+#[event]
+pub struct BasketOfferCreated {
+    pub basket_offer: Pubkey,
+    pub id: u64,
+    pub maker: Pubkey,
+    /// Offered legs as received by their vaults, net of any transfer fee
+    pub offered: Vec<BasketLeg>,
+    pub wanted: Vec<BasketLeg>,
+    pub expires_at: Option<i64>,
+}
+
+#[event]
+pub struct BasketOfferTaken {
+    pub basket_offer: Pubkey,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    /// Fee sent to the escrow fee account for each offered leg, in order
+    pub offered_fee_amounts: Vec<u64>,
+    /// Fee sent to the escrow fee account for each wanted leg, in order
+    pub wanted_fee_amounts: Vec<u64>,
+}
+
+#[event]
+pub struct BasketOfferCancelled {
+    pub basket_offer: Pubkey,
+    pub maker: Pubkey,
+    /// Whether the basket offer was reclaimed after expiring rather than cancelled by the maker
+    pub expired: bool,
+}
+
+#[event]
+pub struct ReferrerRegistered {
+    pub referrer: Pubkey,
+}
+
+#[event]
+pub struct ReferrerRemoved {
+    pub referrer: Pubkey,
+}
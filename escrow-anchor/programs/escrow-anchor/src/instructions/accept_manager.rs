@@ -0,0 +1,40 @@
+use crate::errors::EscrowError;
+use crate::events::ManagerChanged;
+use crate::state::EscrowState;
+use anchor_lang::prelude::*;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AcceptManager<'info> {
+    #[account(mut, seeds = [EscrowState::SEED], bump = escrow_state.bump)]
+    pub escrow_state: Account<'info, EscrowState>,
+    pub new_manager: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<AcceptManager>) -> Result<()> {
+    let AcceptManager {
+        escrow_state,
+        new_manager,
+        ..
+    } = ctx.accounts;
+
+    if escrow_state.pending_manager != Some(new_manager.key()) {
+        return Err(EscrowError::PendingManagerMismatch.into());
+    }
+
+    let previous_manager = escrow_state.manager;
+
+    escrow_state.pending_manager = None;
+
+    let escrow_state_info =
+        EscrowState::write(escrow_state, Some(new_manager.key()), None, None, None)?;
+
+    msg!("Updated escrow manager: {:?}", escrow_state_info);
+
+    emit_cpi!(ManagerChanged {
+        previous_manager,
+        new_manager: escrow_state_info.manager,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,105 @@
+use crate::errors::EscrowError;
+use crate::events::BasketOfferCancelled;
+use crate::state::BasketOffer;
+use crate::utils::{
+    assert_associated_token_account, close_token_account, get_token_account_amount,
+    harvest_withheld_fees, load_mint, transfer_token,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::TokenInterface;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelBasketOffer<'info> {
+    #[account(mut, seeds = [BasketOffer::SEED_PREFIX, maker.key().as_ref(), basket_offer.id.to_le_bytes().as_ref()], bump = basket_offer.bump, close = funding_account)]
+    pub basket_offer: Account<'info, BasketOffer>,
+    #[account(mut, address = basket_offer.maker)]
+    pub maker: Signer<'info>,
+    #[account(mut)]
+    pub funding_account: Signer<'info>,
+}
+
+/// Return every offered leg of a basket to the maker and close its vaults.
+///
+/// The remaining accounts are, for each offered leg in order, its mint, its token program,
+/// the basket offer's vault and the maker's ATA for the mint. Cancelling is never paused, so makers can
+/// always exit.
+pub fn handler<'info>(ctx: Context<'_, '_, 'info, 'info, CancelBasketOffer<'info>>) -> Result<()> {
+    let CancelBasketOffer {
+        basket_offer,
+        maker,
+        funding_account,
+        ..
+    } = ctx.accounts;
+
+    if ctx.remaining_accounts.len() != basket_offer.offered.len() * 4 {
+        return Err(EscrowError::TokenAccountMissing.into());
+    }
+
+    let basket_offer_signer_seeds = &[
+        BasketOffer::SEED_PREFIX,
+        maker.key.as_ref(),
+        &basket_offer.id.to_le_bytes(),
+        &[basket_offer.bump],
+    ];
+
+    for (leg, accounts) in basket_offer
+        .offered
+        .iter()
+        .zip(ctx.remaining_accounts.chunks(4))
+    {
+        let [mint_account, token_program, vault_account, maker_token_account] = accounts else {
+            return Err(EscrowError::TokenAccountMissing.into());
+        };
+
+        let token_program = Interface::<TokenInterface>::try_from(token_program)?;
+        let mint_account = load_mint(mint_account, &leg.mint, &token_program.key())?;
+
+        assert_associated_token_account(
+            vault_account,
+            &basket_offer.key(),
+            &leg.mint,
+            &token_program.key(),
+        )?;
+        assert_associated_token_account(
+            maker_token_account,
+            maker.key,
+            &leg.mint,
+            &token_program.key(),
+        )?;
+
+        transfer_token(
+            token_program.to_account_info().clone(),
+            vault_account.clone(),
+            &mint_account,
+            maker_token_account.clone(),
+            basket_offer.to_account_info().clone(),
+            get_token_account_amount(vault_account)?,
+            Some(basket_offer_signer_seeds),
+        )?;
+
+        harvest_withheld_fees(
+            token_program.to_account_info().clone(),
+            &mint_account,
+            vault_account.clone(),
+        )?;
+
+        close_token_account(
+            token_program.to_account_info().clone(),
+            vault_account.clone(),
+            basket_offer.to_account_info().clone(),
+            funding_account.to_account_info().clone(),
+            Some(basket_offer_signer_seeds),
+        )?;
+    }
+
+    msg!("Basket offer cancelled : {}", basket_offer.key());
+
+    emit_cpi!(BasketOfferCancelled {
+        basket_offer: basket_offer.key(),
+        maker: maker.key(),
+        expired: false,
+    });
+
+    Ok(())
+}
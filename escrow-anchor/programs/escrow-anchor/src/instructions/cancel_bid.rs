@@ -0,0 +1,117 @@
+use crate::errors::EscrowError;
+use crate::events::OfferCancelled;
+use crate::state::{Offer, OfferSide};
+use crate::utils::{
+    assert_received_net, close_token_account, harvest_withheld_fees, is_native_mint,
+    transfer_token, unwrap_sol,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelBid<'info> {
+    #[account(mut, seeds = [Offer::SEED_PREFIX, maker.key().as_ref(), escrow_account.id.to_le_bytes().as_ref()], bump = escrow_account.bump, close = funding_account)]
+    pub escrow_account: Account<'info, Offer>,
+    #[account(mut, address = escrow_account.token_mint_b, mint::token_program = token_program)]
+    pub token_b_mint_account: InterfaceAccount<'info, Mint>,
+    #[account(init_if_needed, payer=funding_account, associated_token::mint = token_b_mint_account, associated_token::authority = maker, associated_token::token_program = token_program)]
+    pub maker_token_b_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut,associated_token::mint = token_b_mint_account, associated_token::authority = escrow_account, associated_token::token_program = token_program)]
+    pub escrow_token_b_vault_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, address = escrow_account.maker)]
+    pub maker: Signer<'info>,
+    #[account(mut)]
+    pub funding_account: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Return a bid's token B to its maker and close it.
+///
+/// Cancelling is never paused, so makers can always exit.
+pub fn handler(ctx: Context<CancelBid>) -> Result<()> {
+    let CancelBid {
+        escrow_account,
+        token_b_mint_account,
+        maker_token_b_account,
+        escrow_token_b_vault_account,
+        maker,
+        funding_account,
+        token_program,
+        ..
+    } = ctx.accounts;
+
+    if escrow_account.side != OfferSide::Bid {
+        return Err(EscrowError::OfferSideMismatch.into());
+    }
+
+    let escrow_account_signer_seeds = &[
+        Offer::SEED_PREFIX,
+        maker.key.as_ref(),
+        &escrow_account.id.to_le_bytes(),
+        &[escrow_account.bump],
+    ];
+
+    let vault_amount_b = escrow_token_b_vault_account.amount;
+    let maker_token_b_amount_before_transfer = maker_token_b_account.amount;
+
+    transfer_token(
+        token_program.to_account_info().clone(),
+        escrow_token_b_vault_account.to_account_info().clone(),
+        token_b_mint_account,
+        maker_token_b_account.to_account_info().clone(),
+        escrow_account.to_account_info().clone(),
+        vault_amount_b,
+        Some(escrow_account_signer_seeds),
+    )?;
+
+    maker_token_b_account.reload()?;
+
+    assert_received_net(
+        "maker token B",
+        token_b_mint_account,
+        maker_token_b_account.amount,
+        maker_token_b_amount_before_transfer,
+        vault_amount_b,
+    )?;
+
+    if is_native_mint(&token_b_mint_account.key()) {
+        unwrap_sol(
+            token_program.to_account_info().clone(),
+            maker_token_b_account.to_account_info().clone(),
+            maker.to_account_info().clone(),
+            maker_token_b_amount_before_transfer,
+        )?;
+    }
+
+    harvest_withheld_fees(
+        token_program.to_account_info().clone(),
+        token_b_mint_account,
+        escrow_token_b_vault_account.to_account_info().clone(),
+    )?;
+
+    close_token_account(
+        token_program.to_account_info().clone(),
+        escrow_token_b_vault_account.to_account_info().clone(),
+        escrow_account.to_account_info().clone(),
+        funding_account.to_account_info().clone(),
+        Some(escrow_account_signer_seeds),
+    )?;
+
+    msg!("Bid cancelled : {}", escrow_account.key());
+
+    emit_cpi!(OfferCancelled {
+        offer: escrow_account.key(),
+        maker: maker.key(),
+        side: OfferSide::Bid,
+        returned_amount: vault_amount_b,
+        expired: false,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use crate::errors::EscrowError;
+use crate::events::ManagerTransferCancelled;
+use crate::state::EscrowState;
+use anchor_lang::prelude::*;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelManagerTransfer<'info> {
+    #[account(mut, seeds = [EscrowState::SEED], bump = escrow_state.bump)]
+    pub escrow_state: Account<'info, EscrowState>,
+    pub escrow_manager: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<CancelManagerTransfer>) -> Result<()> {
+    let CancelManagerTransfer {
+        escrow_state,
+        escrow_manager,
+        ..
+    } = ctx.accounts;
+
+    if escrow_manager.key() != escrow_state.manager {
+        return Err(EscrowError::ManagerKeyUnauthorized.into());
+    }
+
+    let cancelled_manager = escrow_state
+        .pending_manager
+        .take()
+        .ok_or(EscrowError::NoPendingManager)?;
+
+    let escrow_state_info = EscrowState::write(escrow_state, None, None, None, None)?;
+
+    msg!("Cancelled escrow manager transfer: {:?}", escrow_state_info);
+
+    emit_cpi!(ManagerTransferCancelled {
+        manager: escrow_state.manager,
+        cancelled_manager,
+    });
+
+    Ok(())
+}
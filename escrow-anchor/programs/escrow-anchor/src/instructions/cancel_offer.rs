@@ -1,34 +1,42 @@
 use crate::errors::EscrowError;
-use crate::state::Offer;
-use crate::utils::transfer_token;
+use crate::events::OfferCancelled;
+use crate::state::{Offer, OfferSide};
+use crate::utils::{
+    assert_received_net, harvest_withheld_fees, is_native_mint, transfer_token, unwrap_sol,
+};
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{close_account, CloseAccount, Mint, Token, TokenAccount},
+    token_interface::{close_account, CloseAccount, Mint, TokenAccount, TokenInterface},
 };
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct CancelOffer<'info> {
     #[account(mut, seeds = [Offer::SEED_PREFIX, maker.key().as_ref(), escrow_account.id.to_le_bytes().as_ref()], bump = escrow_account.bump, close = funding_account)]
     pub escrow_account: Account<'info, Offer>,
-    #[account(address = escrow_account.token_mint_a)]
-    pub token_a_mint_account: Account<'info, Mint>,
-    #[account(init_if_needed, payer=funding_account, associated_token::mint = token_a_mint_account, associated_token::authority = maker)]
-    pub maker_token_a_account: Account<'info, TokenAccount>,
-    #[account(mut,associated_token::mint = token_a_mint_account, associated_token::authority = escrow_account)]
-    pub escrow_token_a_vault_account: Account<'info, TokenAccount>,
-    #[account(address = escrow_account.maker)]
+    #[account(mut, address = escrow_account.token_mint_a, mint::token_program = token_program)]
+    pub token_a_mint_account: InterfaceAccount<'info, Mint>,
+    #[account(init_if_needed, payer=funding_account, associated_token::mint = token_a_mint_account, associated_token::authority = maker, associated_token::token_program = token_program)]
+    pub maker_token_a_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut,associated_token::mint = token_a_mint_account, associated_token::authority = escrow_account, associated_token::token_program = token_program)]
+    pub escrow_token_a_vault_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, address = escrow_account.maker)]
     pub maker: Signer<'info>,
     #[account(mut)]
     pub funding_account: Signer<'info>,
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
+/// Return an offer's token A to its maker and close it.
+///
+/// Cancelling is never paused, so makers can always exit.
 pub fn handler(ctx: Context<CancelOffer>) -> Result<()> {
     let CancelOffer {
         escrow_account,
+        token_a_mint_account,
         maker_token_a_account,
         escrow_token_a_vault_account,
         maker,
@@ -37,6 +45,10 @@ pub fn handler(ctx: Context<CancelOffer>) -> Result<()> {
         ..
     } = ctx.accounts;
 
+    if escrow_account.side != OfferSide::Ask {
+        return Err(EscrowError::OfferSideMismatch.into());
+    }
+
     let escrow_account_signer_seeds = &[
         Offer::SEED_PREFIX,
         maker.key.as_ref(),
@@ -50,6 +62,7 @@ pub fn handler(ctx: Context<CancelOffer>) -> Result<()> {
     transfer_token(
         token_program.to_account_info().clone(),
         escrow_token_a_vault_account.to_account_info().clone(),
+        token_a_mint_account,
         maker_token_a_account.to_account_info().clone(),
         escrow_account.to_account_info().clone(),
         vault_amount_a,
@@ -58,12 +71,28 @@ pub fn handler(ctx: Context<CancelOffer>) -> Result<()> {
 
     maker_token_a_account.reload()?;
 
-    assert_eq!(
+    assert_received_net(
+        "maker token A",
+        token_a_mint_account,
         maker_token_a_account.amount,
-        maker_token_a_amount_before_transfer
-            .checked_add(vault_amount_a)
-            .ok_or(EscrowError::MathError)?
-    );
+        maker_token_a_amount_before_transfer,
+        vault_amount_a,
+    )?;
+
+    if is_native_mint(&token_a_mint_account.key()) {
+        unwrap_sol(
+            token_program.to_account_info().clone(),
+            maker_token_a_account.to_account_info().clone(),
+            maker.to_account_info().clone(),
+            maker_token_a_amount_before_transfer,
+        )?;
+    }
+
+    harvest_withheld_fees(
+        token_program.to_account_info().clone(),
+        token_a_mint_account,
+        escrow_token_a_vault_account.to_account_info().clone(),
+    )?;
 
     let close_account_cpi = CpiContext::new(
         token_program.to_account_info().clone(),
@@ -76,5 +105,13 @@ pub fn handler(ctx: Context<CancelOffer>) -> Result<()> {
 
     close_account(close_account_cpi.with_signer(&[escrow_account_signer_seeds]))?;
 
+    emit_cpi!(OfferCancelled {
+        offer: escrow_account.key(),
+        maker: maker.key(),
+        side: OfferSide::Ask,
+        returned_amount: vault_amount_a,
+        expired: false,
+    });
+
     Ok(())
 }
@@ -0,0 +1,26 @@
+use crate::events::FeeSplitConfigUpdated;
+use crate::state::{EscrowState, FeeSplitConfig};
+use anchor_lang::prelude::*;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CloseFeeSplitConfig<'info> {
+    #[account(seeds = [EscrowState::SEED], bump = escrow_state.bump)]
+    pub escrow_state: Account<'info, EscrowState>,
+    #[account(mut, address = escrow_state.manager)]
+    pub escrow_manager: Signer<'info>,
+    #[account(mut, seeds = [FeeSplitConfig::SEED], bump = fee_split_config.bump, close = escrow_manager)]
+    pub fee_split_config: Account<'info, FeeSplitConfig>,
+}
+
+/// Remove the fee split, so the manager collects fees with `collect_fee` again.
+pub fn handler(ctx: Context<CloseFeeSplitConfig>) -> Result<()> {
+    msg!(
+        "Closed fee split config : {}",
+        ctx.accounts.fee_split_config.key()
+    );
+
+    emit_cpi!(FeeSplitConfigUpdated { recipients: vec![] });
+
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use crate::events::FeesUpdated;
+use crate::state::{EscrowState, PairFeeConfig};
+use anchor_lang::prelude::*;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClosePairFeeConfig<'info> {
+    #[account(seeds = [EscrowState::SEED], bump = escrow_state.bump)]
+    pub escrow_state: Account<'info, EscrowState>,
+    #[account(mut, address = escrow_state.manager)]
+    pub escrow_manager: Signer<'info>,
+    #[account(mut, seeds = [PairFeeConfig::SEED_PREFIX, pair_fee_config.token_mint_a.as_ref(), pair_fee_config.token_mint_b.as_ref()], bump = pair_fee_config.bump, close = escrow_manager)]
+    pub pair_fee_config: Account<'info, PairFeeConfig>,
+}
+
+/// Remove a pair's fee override, so its offers fall back to the global fees.
+pub fn handler(ctx: Context<ClosePairFeeConfig>) -> Result<()> {
+    let ClosePairFeeConfig {
+        escrow_state,
+        pair_fee_config,
+        ..
+    } = ctx.accounts;
+
+    msg!("Closed pair fee config : {}", pair_fee_config.key());
+
+    emit_cpi!(FeesUpdated {
+        token_mint_a: Some(pair_fee_config.token_mint_a),
+        token_mint_b: Some(pair_fee_config.token_mint_b),
+        maker_fee_bps: escrow_state.maker_fee_bps,
+        taker_fee_bps: escrow_state.taker_fee_bps,
+    });
+
+    Ok(())
+}
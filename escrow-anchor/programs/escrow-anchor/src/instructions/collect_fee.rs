@@ -1,9 +1,11 @@
-use crate::state::EscrowState;
-use crate::utils::transfer_token;
+use crate::errors::EscrowError;
+use crate::events::FeesCollected;
+use crate::state::{EscrowState, FeeSplitConfig};
+use crate::utils::{harvest_withheld_fees, load_optional_account, transfer_token};
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{close_account, CloseAccount, Mint, Token, TokenAccount},
+    token_interface::{close_account, CloseAccount, Mint, TokenAccount, TokenInterface},
 };
 
 #[derive(AnchorDeserialize, AnchorSerialize, Debug)]
@@ -11,32 +13,47 @@ pub struct CollectFeeArgs {
     pub should_close_fee_account: bool,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct CollectFee<'info> {
     #[account(seeds = [EscrowState::SEED], bump = escrow_state.bump)]
     pub escrow_state: Account<'info, EscrowState>,
     #[account(mut, address = escrow_state.manager)]
     pub escrow_manager: Signer<'info>,
-    pub token_mint_account: Account<'info, Mint>,
-    #[account(mut, associated_token::mint = token_mint_account , associated_token::authority = escrow_state)]
-    pub escrow_fee_account: Account<'info, TokenAccount>,
-    #[account(init_if_needed, payer = escrow_manager, associated_token::mint = token_mint_account, associated_token::authority = escrow_manager)]
-    pub manager_token_account: Account<'info, TokenAccount>,
-    pub token_program: Program<'info, Token>,
+    /// CHECK : fee split PDA, which is empty unless the manager configured a fee split
+    #[account(seeds = [FeeSplitConfig::SEED], bump)]
+    pub fee_split_config: UncheckedAccount<'info>,
+    #[account(mut, mint::token_program = token_program)]
+    pub token_mint_account: InterfaceAccount<'info, Mint>,
+    #[account(mut, associated_token::mint = token_mint_account , associated_token::authority = escrow_state, associated_token::token_program = token_program)]
+    pub escrow_fee_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(init_if_needed, payer = escrow_manager, associated_token::mint = token_mint_account, associated_token::authority = escrow_manager, associated_token::token_program = token_program)]
+    pub manager_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
+/// Collect the accumulated fees of a mint to the manager.
+///
+/// Once a fee split is configured, fees can only leave through `distribute_fees` until the
+/// manager closes it with `close_fee_split_config`.
 pub fn handler(ctx: Context<CollectFee>, args: CollectFeeArgs) -> Result<()> {
     let CollectFee {
         escrow_state,
         escrow_manager,
+        fee_split_config,
+        token_mint_account,
         escrow_fee_account,
         manager_token_account,
         token_program,
         ..
     } = ctx.accounts;
 
+    if load_optional_account::<FeeSplitConfig>(fee_split_config)?.is_some() {
+        return Err(EscrowError::FeeSplitConfigured.into());
+    }
+
     let fee_amount = escrow_fee_account.amount;
 
     let escrow_state_signer_seeds = &[EscrowState::SEED, &[escrow_state.bump]];
@@ -45,14 +62,27 @@ pub fn handler(ctx: Context<CollectFee>, args: CollectFeeArgs) -> Result<()> {
         transfer_token(
             token_program.to_account_info().clone(),
             escrow_fee_account.to_account_info().clone(),
+            token_mint_account,
             manager_token_account.to_account_info().clone(),
             escrow_state.to_account_info().clone(),
             fee_amount,
             Some(escrow_state_signer_seeds),
         )?;
+
+        emit_cpi!(FeesCollected {
+            token_mint: token_mint_account.key(),
+            recipient_token_account: manager_token_account.key(),
+            amount: fee_amount,
+        });
     }
 
     if args.should_close_fee_account {
+        harvest_withheld_fees(
+            token_program.to_account_info().clone(),
+            token_mint_account,
+            escrow_fee_account.to_account_info().clone(),
+        )?;
+
         close_account(
             CpiContext::new(
                 token_program.to_account_info().clone(),
@@ -0,0 +1,138 @@
+use crate::errors::EscrowError;
+use crate::events::AuctionCreated;
+use crate::state::{Auction, EscrowState, MintConfig};
+use crate::utils::{assert_received_net, load_optional_account, transfer_token};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug)]
+pub struct CreateAuctionArgs {
+    pub id: u64,
+    pub token_a_amount: u64,
+    pub min_bid_amount: u64,
+    pub min_bid_increment: u64,
+    pub end_time: i64,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(args: CreateAuctionArgs)]
+pub struct CreateAuction<'info> {
+    #[account(seeds = [EscrowState::SEED], bump = escrow_state.bump)]
+    pub escrow_state: Box<Account<'info, EscrowState>>,
+    #[account(init, space = 8 + Auction::INIT_SPACE, payer = funding_account, seeds = [Auction::SEED_PREFIX, maker.key().as_ref(), args.id.to_le_bytes().as_ref()], bump)]
+    pub auction: Box<Account<'info, Auction>>,
+    #[account(mint::token_program = token_program)]
+    pub token_a_mint_account: Box<InterfaceAccount<'info, Mint>>,
+    pub token_b_mint_account: Box<InterfaceAccount<'info, Mint>>,
+    /// CHECK : mint config PDA of token A, which is empty unless the manager created it
+    #[account(seeds = [MintConfig::SEED_PREFIX, token_a_mint_account.key().as_ref()], bump)]
+    pub token_a_mint_config: UncheckedAccount<'info>,
+    /// CHECK : mint config PDA of token B, which is empty unless the manager created it
+    #[account(seeds = [MintConfig::SEED_PREFIX, token_b_mint_account.key().as_ref()], bump)]
+    pub token_b_mint_config: UncheckedAccount<'info>,
+    #[account(mut, associated_token::mint = token_a_mint_account, associated_token::authority = maker, associated_token::token_program = token_program)]
+    pub maker_token_a_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(init, payer = funding_account, associated_token::mint = token_a_mint_account, associated_token::authority = auction, associated_token::token_program = token_program)]
+    pub auction_token_a_vault_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    #[account(mut)]
+    pub funding_account: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Escrow token A in an auction vault for bidders to compete on until `end_time`.
+pub fn handler(ctx: Context<CreateAuction>, args: CreateAuctionArgs) -> Result<()> {
+    let CreateAuction {
+        escrow_state,
+        auction,
+        token_a_mint_account,
+        token_b_mint_account,
+        token_a_mint_config,
+        token_b_mint_config,
+        maker_token_a_account,
+        auction_token_a_vault_account,
+        maker,
+        funding_account,
+        token_program,
+        ..
+    } = ctx.accounts;
+
+    if escrow_state.make_paused {
+        return Err(EscrowError::MakePaused.into());
+    }
+
+    if !escrow_state
+        .is_mint_permitted(load_optional_account::<MintConfig>(token_a_mint_config)?.as_ref())
+        || !escrow_state
+            .is_mint_permitted(load_optional_account::<MintConfig>(token_b_mint_config)?.as_ref())
+    {
+        return Err(EscrowError::MintNotPermitted.into());
+    }
+
+    // Token A and the bids are held in ATAs of the auction, which must not be the same account
+    if token_a_mint_account.key() == token_b_mint_account.key() {
+        return Err(EscrowError::IdenticalMints.into());
+    }
+
+    if args.end_time <= Clock::get()?.unix_timestamp {
+        return Err(EscrowError::InvalidExpiry.into());
+    }
+
+    transfer_token(
+        token_program.to_account_info().clone(),
+        maker_token_a_account.to_account_info().clone(),
+        token_a_mint_account,
+        auction_token_a_vault_account.to_account_info().clone(),
+        maker.to_account_info().clone(),
+        args.token_a_amount,
+        None,
+    )?;
+
+    auction_token_a_vault_account.reload()?;
+
+    let token_a_received_amount = assert_received_net(
+        "vault token A",
+        token_a_mint_account,
+        auction_token_a_vault_account.amount,
+        0,
+        args.token_a_amount,
+    )?;
+
+    auction.min_bid_amount = args.min_bid_amount;
+    auction.min_bid_increment = args.min_bid_increment;
+    auction.funder = funding_account.key();
+
+    let auction_info = Auction::write(
+        auction,
+        Some(args.id),
+        Some(maker.key()),
+        Some(token_a_mint_account.key()),
+        Some(token_b_mint_account.key()),
+        Some(token_a_received_amount),
+        Some(args.end_time),
+        Some(ctx.bumps.auction),
+    )?;
+
+    msg!("Auction created successfully : {:?}", auction_info);
+
+    emit_cpi!(AuctionCreated {
+        auction: auction.key(),
+        id: auction_info.id,
+        maker: auction_info.maker,
+        token_mint_a: auction_info.token_mint_a,
+        token_mint_b: auction_info.token_mint_b,
+        token_a_amount: auction_info.token_a_amount,
+        min_bid_amount: auction_info.min_bid_amount,
+        min_bid_increment: auction_info.min_bid_increment,
+        end_time: auction_info.end_time,
+    });
+
+    Ok(())
+}
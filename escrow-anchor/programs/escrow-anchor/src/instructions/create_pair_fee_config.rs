@@ -0,0 +1,59 @@
+use crate::events::FeesUpdated;
+use crate::state::{EscrowState, PairFeeConfig};
+use crate::utils::assert_is_bps_in_range;
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+#[derive(AnchorDeserialize, AnchorSerialize, Debug, Default)]
+pub struct CreatePairFeeConfigArgs {
+    pub maker_fee_bps: u16,
+    pub taker_fee_bps: u16,
+    pub volume_per_token_a: u64,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CreatePairFeeConfig<'info> {
+    #[account(seeds = [EscrowState::SEED], bump = escrow_state.bump)]
+    pub escrow_state: Account<'info, EscrowState>,
+    #[account(mut, address = escrow_state.manager)]
+    pub escrow_manager: Signer<'info>,
+    pub token_a_mint_account: InterfaceAccount<'info, Mint>,
+    pub token_b_mint_account: InterfaceAccount<'info, Mint>,
+    #[account(init, payer = escrow_manager, space = 8 + PairFeeConfig::INIT_SPACE, seeds = [PairFeeConfig::SEED_PREFIX, token_a_mint_account.key().as_ref(), token_b_mint_account.key().as_ref()], bump)]
+    pub pair_fee_config: Account<'info, PairFeeConfig>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<CreatePairFeeConfig>, args: CreatePairFeeConfigArgs) -> Result<()> {
+    assert_is_bps_in_range(args.maker_fee_bps)?;
+    assert_is_bps_in_range(args.taker_fee_bps)?;
+
+    let CreatePairFeeConfig {
+        token_a_mint_account,
+        token_b_mint_account,
+        pair_fee_config,
+        ..
+    } = ctx.accounts;
+
+    let pair_fee_config_info = PairFeeConfig::write(
+        pair_fee_config,
+        Some(token_a_mint_account.key()),
+        Some(token_b_mint_account.key()),
+        Some(args.maker_fee_bps),
+        Some(args.taker_fee_bps),
+        Some(args.volume_per_token_a),
+        Some(ctx.bumps.pair_fee_config),
+    )?;
+
+    msg!("Created pair fee config : {:?}", pair_fee_config_info);
+
+    emit_cpi!(FeesUpdated {
+        token_mint_a: Some(pair_fee_config_info.token_mint_a),
+        token_mint_b: Some(pair_fee_config_info.token_mint_b),
+        maker_fee_bps: pair_fee_config_info.maker_fee_bps,
+        taker_fee_bps: pair_fee_config_info.taker_fee_bps,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,38 @@
+use crate::events::TraderStatsCreated;
+use crate::state::TraderStats;
+use anchor_lang::prelude::*;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CreateTraderStats<'info> {
+    #[account(init, payer = funding_account, space = 8 + TraderStats::INIT_SPACE, seeds = [TraderStats::SEED_PREFIX, trader.key().as_ref()], bump)]
+    pub trader_stats: Account<'info, TraderStats>,
+    pub trader: Signer<'info>,
+    #[account(mut)]
+    pub funding_account: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Opt a trader into fee tiers. Fills only record volume for traders with stats, so
+/// trades never pay rent for them.
+pub fn handler(ctx: Context<CreateTraderStats>) -> Result<()> {
+    let CreateTraderStats {
+        trader_stats,
+        trader,
+        ..
+    } = ctx.accounts;
+
+    let trader_stats_info = TraderStats::write(
+        trader_stats,
+        Some(trader.key()),
+        Some(ctx.bumps.trader_stats),
+    )?;
+
+    msg!("Created trader stats : {:?}", trader_stats_info);
+
+    emit_cpi!(TraderStatsCreated {
+        trader: trader_stats_info.trader,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use crate::errors::EscrowError;
+use crate::events::FeesCollected;
+use crate::state::{EscrowState, FeeSplitConfig};
+use crate::utils::transfer_token;
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::{
+        create_idempotent, get_associated_token_address_with_program_id, AssociatedToken, Create,
+    },
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct DistributeFees<'info> {
+    #[account(seeds = [EscrowState::SEED], bump = escrow_state.bump)]
+    pub escrow_state: Account<'info, EscrowState>,
+    #[account(seeds = [FeeSplitConfig::SEED], bump = fee_split_config.bump)]
+    pub fee_split_config: Account<'info, FeeSplitConfig>,
+    #[account(mint::token_program = token_program)]
+    pub token_mint_account: InterfaceAccount<'info, Mint>,
+    #[account(mut, associated_token::mint = token_mint_account, associated_token::authority = escrow_state, associated_token::token_program = token_program)]
+    pub escrow_fee_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Split the accumulated fees of a mint between the fee split recipients.
+///
+/// Anyone can crank this. Each recipient's wallet and ATA for the mint are passed as a pair
+/// of remaining accounts, in the order of `FeeSplitConfig::recipients`. Missing ATAs are
+/// created, paid for by the payer.
+pub fn handler<'info>(ctx: Context<'_, '_, 'info, 'info, DistributeFees<'info>>) -> Result<()> {
+    let DistributeFees {
+        escrow_state,
+        fee_split_config,
+        token_mint_account,
+        escrow_fee_account,
+        payer,
+        token_program,
+        associated_token_program,
+        system_program,
+        ..
+    } = ctx.accounts;
+
+    let recipient_accounts = ctx.remaining_accounts;
+
+    if recipient_accounts.len() != fee_split_config.recipients.len() * 2 {
+        return Err(EscrowError::TokenAccountMissing.into());
+    }
+
+    let fee_amount = escrow_fee_account.amount;
+    let split_amounts = fee_split_config.get_split_amounts(fee_amount)?;
+
+    let escrow_state_signer_seeds = &[EscrowState::SEED, &[escrow_state.bump]];
+
+    for ((recipient, recipient_accounts), split_amount) in fee_split_config
+        .recipients
+        .iter()
+        .zip(recipient_accounts.chunks_exact(2))
+        .zip(split_amounts)
+    {
+        let (recipient_wallet, recipient_token_account) =
+            (&recipient_accounts[0], &recipient_accounts[1]);
+
+        if recipient_wallet.key() != recipient.wallet
+            || recipient_token_account.key()
+                != get_associated_token_address_with_program_id(
+                    &recipient.wallet,
+                    &token_mint_account.key(),
+                    &token_program.key(),
+                )
+        {
+            return Err(EscrowError::TokenAccountMismatch.into());
+        }
+
+        create_idempotent(CpiContext::new(
+            associated_token_program.to_account_info().clone(),
+            Create {
+                payer: payer.to_account_info().clone(),
+                associated_token: recipient_token_account.clone(),
+                authority: recipient_wallet.clone(),
+                mint: token_mint_account.to_account_info().clone(),
+                system_program: system_program.to_account_info().clone(),
+                token_program: token_program.to_account_info().clone(),
+            },
+        ))?;
+
+        if split_amount != 0 {
+            transfer_token(
+                token_program.to_account_info().clone(),
+                escrow_fee_account.to_account_info().clone(),
+                token_mint_account,
+                recipient_token_account.clone(),
+                escrow_state.to_account_info().clone(),
+                split_amount,
+                Some(escrow_state_signer_seeds),
+            )?;
+
+            emit_cpi!(FeesCollected {
+                token_mint: token_mint_account.key(),
+                recipient_token_account: recipient_token_account.key(),
+                amount: split_amount,
+            });
+        }
+    }
+
+    msg!(
+        "Distributed fees : {} of {}",
+        fee_amount,
+        token_mint_account.key()
+    );
+
+    Ok(())
+}
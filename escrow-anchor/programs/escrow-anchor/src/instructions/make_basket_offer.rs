@@ -0,0 +1,164 @@
+use crate::errors::EscrowError;
+use crate::events::BasketOfferCreated;
+use crate::state::{BasketLeg, BasketOffer, EscrowState};
+use crate::utils::{
+    assert_associated_token_account, assert_received_net, create_associated_token_account,
+    get_token_account_amount, load_mint, load_mint_config, transfer_token,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token_interface::TokenInterface};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug)]
+pub struct MakeBasketOfferArgs {
+    pub id: u64,
+    pub offered: Vec<BasketLeg>,
+    pub wanted: Vec<BasketLeg>,
+    pub expires_at: Option<i64>,
+    pub allowed_taker: Option<Pubkey>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(args: MakeBasketOfferArgs)]
+pub struct MakeBasketOffer<'info> {
+    #[account(seeds = [EscrowState::SEED], bump = escrow_state.bump)]
+    pub escrow_state: Account<'info, EscrowState>,
+    #[account(init, space = 8 + BasketOffer::INIT_SPACE, payer = funding_account, seeds = [BasketOffer::SEED_PREFIX, maker.key().as_ref(), args.id.to_le_bytes().as_ref()], bump)]
+    pub basket_offer: Account<'info, BasketOffer>,
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    #[account(mut)]
+    pub funding_account: Signer<'info>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Escrow every offered leg of a basket in its own vault.
+///
+/// The remaining accounts are, for each offered leg in order, its mint, its token program,
+/// mint config, the maker's token account and the basket offer's ATA for the mint, followed
+/// by the mint config of each wanted leg. Legs may mix SPL Token and Token-2022 mints.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, MakeBasketOffer<'info>>,
+    args: MakeBasketOfferArgs,
+) -> Result<()> {
+    let MakeBasketOffer {
+        escrow_state,
+        basket_offer,
+        maker,
+        funding_account,
+        associated_token_program,
+        system_program,
+        ..
+    } = ctx.accounts;
+
+    if escrow_state.make_paused {
+        return Err(EscrowError::MakePaused.into());
+    }
+
+    BasketOffer::assert_is_valid_legs(&args.offered)?;
+    BasketOffer::assert_is_valid_legs(&args.wanted)?;
+
+    if ctx.remaining_accounts.len() != args.offered.len() * 5 + args.wanted.len() {
+        return Err(EscrowError::TokenAccountMissing.into());
+    }
+
+    let (offered_leg_accounts, wanted_leg_accounts) =
+        ctx.remaining_accounts.split_at(args.offered.len() * 5);
+
+    for (leg, mint_config) in args.wanted.iter().zip(wanted_leg_accounts) {
+        if !escrow_state.is_mint_permitted(load_mint_config(mint_config, &leg.mint)?.as_ref()) {
+            return Err(EscrowError::MintNotPermitted.into());
+        }
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+
+    if matches!(args.expires_at, Some(expires_at) if expires_at <= now) {
+        return Err(EscrowError::InvalidExpiry.into());
+    }
+
+    basket_offer.funder = funding_account.key();
+    basket_offer.created_at = now;
+    basket_offer.expires_at = args.expires_at;
+    basket_offer.allowed_taker = args.allowed_taker;
+
+    let mut offered = Vec::with_capacity(args.offered.len());
+
+    for (leg, accounts) in args.offered.iter().zip(offered_leg_accounts.chunks(5)) {
+        let [mint_account, token_program, mint_config, maker_token_account, vault_account] =
+            accounts
+        else {
+            return Err(EscrowError::TokenAccountMissing.into());
+        };
+
+        let token_program = Interface::<TokenInterface>::try_from(token_program)?;
+        let mint_account = load_mint(mint_account, &leg.mint, &token_program.key())?;
+
+        if !escrow_state.is_mint_permitted(load_mint_config(mint_config, &leg.mint)?.as_ref()) {
+            return Err(EscrowError::MintNotPermitted.into());
+        }
+
+        assert_associated_token_account(
+            vault_account,
+            &basket_offer.key(),
+            &leg.mint,
+            &token_program.key(),
+        )?;
+
+        create_associated_token_account(
+            associated_token_program,
+            funding_account.to_account_info().clone(),
+            vault_account.clone(),
+            basket_offer.to_account_info().clone(),
+            mint_account.to_account_info().clone(),
+            token_program.to_account_info().clone(),
+            system_program.to_account_info().clone(),
+        )?;
+
+        transfer_token(
+            token_program.to_account_info().clone(),
+            maker_token_account.clone(),
+            &mint_account,
+            vault_account.clone(),
+            maker.to_account_info().clone(),
+            leg.amount,
+            None,
+        )?;
+
+        let received_amount = assert_received_net(
+            "basket vault",
+            &mint_account,
+            get_token_account_amount(vault_account)?,
+            0,
+            leg.amount,
+        )?;
+
+        offered.push(BasketLeg {
+            mint: leg.mint,
+            amount: received_amount,
+        });
+    }
+
+    let offer = BasketOffer::write(
+        basket_offer,
+        Some(args.id),
+        Some(maker.key()),
+        Some(offered),
+        Some(args.wanted),
+        Some(ctx.bumps.basket_offer),
+    )?;
+
+    msg!("Basket offer created successfully : {:?}", offer);
+
+    emit_cpi!(BasketOfferCreated {
+        basket_offer: basket_offer.key(),
+        id: offer.id,
+        maker: offer.maker,
+        offered: offer.offered,
+        wanted: offer.wanted,
+        expires_at: offer.expires_at,
+    });
+
+    Ok(())
+}
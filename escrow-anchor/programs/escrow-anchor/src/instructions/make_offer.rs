@@ -1,9 +1,13 @@
-use crate::state::Offer;
-use crate::utils::transfer_token;
+use crate::errors::EscrowError;
+use crate::events::OfferCreated;
+use crate::state::{EscrowState, MintConfig, Offer, OfferKind, OfferSide};
+use crate::utils::{
+    assert_received_net, is_native_mint, load_optional_account, transfer_token, wrap_sol,
+};
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{Mint, Token, TokenAccount},
+    token_interface::{Mint, TokenAccount, TokenInterface},
 };
 
 #[derive(AnchorSerialize, AnchorDeserialize, Debug)]
@@ -11,64 +15,148 @@ pub struct MakeOfferArgs {
     pub id: u64,
     pub token_a_offered_amount: u64,
     pub token_b_wanted_amount: u64,
+    pub expires_at: Option<i64>,
+    pub allowed_taker: Option<Pubkey>,
+    pub taker_merkle_root: Option<[u8; 32]>,
+    pub kind: OfferKind,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 #[instruction(args: MakeOfferArgs)]
 pub struct MakeOffer<'info> {
+    #[account(seeds = [EscrowState::SEED], bump = escrow_state.bump)]
+    pub escrow_state: Account<'info, EscrowState>,
     #[account(init, space=8+Offer::INIT_SPACE, payer = funding_account, seeds = [Offer::SEED_PREFIX,maker.key().as_ref(), args.id.to_le_bytes().as_ref() ], bump)]
     pub escrow_account: Account<'info, Offer>,
-    pub token_a_mint_account: Account<'info, Mint>,
-    pub token_b_mint_account: Account<'info, Mint>,
-    #[account(mut, associated_token::mint = token_a_mint_account, associated_token::authority = maker)]
-    pub maker_token_a_account: Account<'info, TokenAccount>,
-    #[account(init, payer = funding_account,  associated_token::mint = token_a_mint_account, associated_token::authority = escrow_account)]
-    pub escrow_token_a_vault_account: Account<'info, TokenAccount>,
+    #[account(mint::token_program = token_program)]
+    pub token_a_mint_account: InterfaceAccount<'info, Mint>,
+    pub token_b_mint_account: InterfaceAccount<'info, Mint>,
+    /// CHECK : mint config PDA of token A, which is empty unless the manager created it
+    #[account(seeds = [MintConfig::SEED_PREFIX, token_a_mint_account.key().as_ref()], bump)]
+    pub token_a_mint_config: UncheckedAccount<'info>,
+    /// CHECK : mint config PDA of token B, which is empty unless the manager created it
+    #[account(seeds = [MintConfig::SEED_PREFIX, token_b_mint_account.key().as_ref()], bump)]
+    pub token_b_mint_config: UncheckedAccount<'info>,
+    #[account(mut, associated_token::mint = token_a_mint_account, associated_token::authority = maker, associated_token::token_program = token_program)]
+    pub maker_token_a_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(init, payer = funding_account,  associated_token::mint = token_a_mint_account, associated_token::authority = escrow_account, associated_token::token_program = token_program)]
+    pub escrow_token_a_vault_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
     pub maker: Signer<'info>,
     #[account(mut)]
     pub funding_account: Signer<'info>,
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
 pub fn handler(ctx: Context<MakeOffer>, args: MakeOfferArgs) -> Result<()> {
     let MakeOffer {
+        escrow_state,
         escrow_account,
         token_a_mint_account,
         token_b_mint_account,
+        token_a_mint_config,
+        token_b_mint_config,
         maker_token_a_account,
         escrow_token_a_vault_account,
         maker,
+        funding_account,
         token_program,
+        system_program,
         ..
     } = ctx.accounts;
 
+    if escrow_state.make_paused {
+        return Err(EscrowError::MakePaused.into());
+    }
+
+    if !escrow_state
+        .is_mint_permitted(load_optional_account::<MintConfig>(token_a_mint_config)?.as_ref())
+        || !escrow_state
+            .is_mint_permitted(load_optional_account::<MintConfig>(token_b_mint_config)?.as_ref())
+    {
+        return Err(EscrowError::MintNotPermitted.into());
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+
+    if matches!(args.expires_at, Some(expires_at) if expires_at <= now) {
+        return Err(EscrowError::InvalidExpiry.into());
+    }
+
+    escrow_account.funder = funding_account.key();
+    escrow_account.created_at = now;
+    escrow_account.expires_at = args.expires_at;
+    escrow_account.allowed_taker = args.allowed_taker;
+    escrow_account.taker_merkle_root = args.taker_merkle_root;
+    escrow_account.kind = args.kind;
+    escrow_account.side = OfferSide::Ask;
+
+    if is_native_mint(&token_a_mint_account.key()) {
+        // Native SOL is taken from the maker's lamports and wrapped into the vault
+        wrap_sol(
+            system_program.to_account_info().clone(),
+            token_program.to_account_info().clone(),
+            maker.to_account_info().clone(),
+            escrow_token_a_vault_account.to_account_info().clone(),
+            args.token_a_offered_amount,
+        )?;
+    } else {
+        let maker_token_a_account = maker_token_a_account
+            .as_ref()
+            .ok_or(EscrowError::TokenAccountMissing)?;
+
+        transfer_token(
+            token_program.to_account_info().clone(),
+            maker_token_a_account.to_account_info().clone(),
+            token_a_mint_account,
+            escrow_token_a_vault_account.to_account_info().clone(),
+            maker.to_account_info().clone(),
+            args.token_a_offered_amount,
+            None,
+        )?;
+    }
+
+    escrow_token_a_vault_account.reload()?;
+
+    let token_a_received_amount = assert_received_net(
+        "vault token A",
+        token_a_mint_account,
+        escrow_token_a_vault_account.amount,
+        0,
+        args.token_a_offered_amount,
+    )?;
+
     let offer = Offer::write(
         escrow_account,
         Some(args.id),
         Some(maker.key()),
         Some(token_a_mint_account.key()),
         Some(token_b_mint_account.key()),
+        Some(token_a_received_amount),
         Some(args.token_b_wanted_amount),
         Some(ctx.bumps.escrow_account),
     )?;
 
-    transfer_token(
-        token_program.to_account_info().clone(),
-        maker_token_a_account.to_account_info().clone(),
-        escrow_token_a_vault_account.to_account_info().clone(),
-        maker.to_account_info().clone(),
-        args.token_a_offered_amount,
-        None,
-    )?;
-
-    escrow_token_a_vault_account.reload()?;
-    let vault_token_amount = escrow_token_a_vault_account.amount;
-
-    assert_eq!(vault_token_amount, args.token_a_offered_amount);
+    offer.assert_is_valid_amounts()?;
+    offer.assert_is_valid_kind()?;
 
     msg!("Offer created successfully : {:?}", offer);
 
+    emit_cpi!(OfferCreated {
+        offer: escrow_account.key(),
+        id: offer.id,
+        maker: offer.maker,
+        token_mint_a: offer.token_mint_a,
+        token_mint_b: offer.token_mint_b,
+        token_a_offered_amount: offer.token_a_offered_amount,
+        token_b_wanted_amount: offer.token_b_wanted_amount,
+        expires_at: offer.expires_at,
+        kind: offer.kind,
+        side: offer.side,
+    });
+
     Ok(())
 }
@@ -0,0 +1,415 @@
+use crate::{
+    errors::EscrowError,
+    events::OffersMatched,
+    state::{EscrowState, MintConfig, Offer, PairFeeConfig, TraderStats},
+    utils::{
+        assert_received_net, close_token_account, harvest_withheld_fees, load_optional_account,
+        store_optional_account, transfer_token,
+    },
+};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct MatchOffers<'info> {
+    #[account(seeds = [EscrowState::SEED], bump = escrow_state.bump)]
+    pub escrow_state: Box<Account<'info, EscrowState>>,
+    #[account(mut, seeds = [Offer::SEED_PREFIX, ask_maker.key().as_ref(), ask_offer.id.to_le_bytes().as_ref()], bump = ask_offer.bump)]
+    pub ask_offer: Box<Account<'info, Offer>>,
+    #[account(mut, seeds = [Offer::SEED_PREFIX, bid_maker.key().as_ref(), bid_offer.id.to_le_bytes().as_ref()], bump = bid_offer.bump)]
+    pub bid_offer: Box<Account<'info, Offer>>,
+    #[account(mut, address = ask_offer.token_mint_a, mint::token_program = token_a_program)]
+    pub token_a_mint_account: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut, address = ask_offer.token_mint_b, mint::token_program = token_b_program)]
+    pub token_b_mint_account: Box<InterfaceAccount<'info, Mint>>,
+    /// CHECK : mint config PDA of token A, which is empty unless the manager created it
+    #[account(seeds = [MintConfig::SEED_PREFIX, token_a_mint_account.key().as_ref()], bump)]
+    pub token_a_mint_config: UncheckedAccount<'info>,
+    /// CHECK : mint config PDA of token B, which is empty unless the manager created it
+    #[account(seeds = [MintConfig::SEED_PREFIX, token_b_mint_account.key().as_ref()], bump)]
+    pub token_b_mint_config: UncheckedAccount<'info>,
+    /// CHECK : fee override PDA of the trading pair, which is empty unless the manager created it
+    #[account(seeds = [PairFeeConfig::SEED_PREFIX, token_a_mint_account.key().as_ref(), token_b_mint_account.key().as_ref()], bump)]
+    pub pair_fee_config: UncheckedAccount<'info>,
+    /// CHECK : fee tier stats PDA of the ask maker, which is empty unless the trader created it
+    #[account(mut, seeds = [TraderStats::SEED_PREFIX, ask_maker.key().as_ref()], bump)]
+    pub ask_maker_trader_stats: UncheckedAccount<'info>,
+    /// CHECK : fee tier stats PDA of the bid maker, which is empty unless the trader created it
+    #[account(mut, seeds = [TraderStats::SEED_PREFIX, bid_maker.key().as_ref()], bump)]
+    pub bid_maker_trader_stats: UncheckedAccount<'info>,
+    #[account(init_if_needed, payer = cranker, associated_token::mint = token_b_mint_account, associated_token::authority = ask_maker, associated_token::token_program = token_b_program)]
+    pub ask_maker_token_b_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(init_if_needed, payer = cranker, associated_token::mint = token_a_mint_account, associated_token::authority = bid_maker, associated_token::token_program = token_a_program)]
+    pub bid_maker_token_a_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(init_if_needed, payer = cranker, associated_token::mint = token_b_mint_account, associated_token::authority = cranker, associated_token::token_program = token_b_program)]
+    pub cranker_token_b_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(init_if_needed, payer = cranker, associated_token::mint = token_a_mint_account, associated_token::authority = escrow_state, associated_token::token_program = token_a_program)]
+    pub escrow_token_a_fee_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(init_if_needed, payer = cranker, associated_token::mint = token_b_mint_account, associated_token::authority = escrow_state, associated_token::token_program = token_b_program)]
+    pub escrow_token_b_fee_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, associated_token::mint = token_a_mint_account, associated_token::authority = ask_offer, associated_token::token_program = token_a_program)]
+    pub ask_token_a_vault_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, associated_token::mint = token_b_mint_account, associated_token::authority = bid_offer, associated_token::token_program = token_b_program)]
+    pub bid_token_b_vault_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    /// CHECK : address of the ask maker wallet
+    #[account(address = ask_offer.maker)]
+    pub ask_maker: UncheckedAccount<'info>,
+    /// CHECK : address of the bid maker wallet
+    #[account(address = bid_offer.maker)]
+    pub bid_maker: UncheckedAccount<'info>,
+    /// CHECK : address of the account which funded the ask, receives the rent back once filled
+    #[account(mut, address = ask_offer.funder)]
+    pub ask_funder: UncheckedAccount<'info>,
+    /// CHECK : address of the account which funded the bid, receives the rent back once filled
+    #[account(mut, address = bid_offer.funder)]
+    pub bid_funder: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+    pub token_a_program: Interface<'info, TokenInterface>,
+    pub token_b_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Match an ask against a crossing bid on the same pair.
+///
+/// The older offer rests and sets the execution price: the ask maker is paid at that price
+/// and pays the maker fee if it rests, or the taker fee otherwise, and the same goes for
+/// the bid maker's token A. The bid releases token B on its own terms, so when the ask
+/// rests the token B above the ask's price is paid to the cranker, less the taker fee.
+pub fn handler(ctx: Context<MatchOffers>) -> Result<()> {
+    let MatchOffers {
+        escrow_state,
+        ask_offer,
+        bid_offer,
+        token_a_mint_account,
+        token_b_mint_account,
+        token_a_mint_config,
+        token_b_mint_config,
+        pair_fee_config,
+        ask_maker_trader_stats,
+        bid_maker_trader_stats,
+        ask_maker_token_b_account,
+        bid_maker_token_a_account,
+        cranker_token_b_account,
+        escrow_token_a_fee_account,
+        escrow_token_b_fee_account,
+        ask_token_a_vault_account,
+        bid_token_b_vault_account,
+        ask_maker,
+        bid_maker,
+        ask_funder,
+        bid_funder,
+        cranker,
+        token_a_program,
+        token_b_program,
+        ..
+    } = ctx.accounts;
+
+    if escrow_state.take_paused {
+        return Err(EscrowError::TakePaused.into());
+    }
+
+    if bid_offer.token_mint_a != ask_offer.token_mint_a
+        || bid_offer.token_mint_b != ask_offer.token_mint_b
+    {
+        return Err(EscrowError::OfferPairMismatch.into());
+    }
+
+    let token_a_mint_config = load_optional_account::<MintConfig>(token_a_mint_config)?;
+    let token_b_mint_config = load_optional_account::<MintConfig>(token_b_mint_config)?;
+
+    if !escrow_state.is_mint_permitted(token_a_mint_config.as_ref())
+        || !escrow_state.is_mint_permitted(token_b_mint_config.as_ref())
+    {
+        return Err(EscrowError::MintNotPermitted.into());
+    }
+
+    // The maker and taker volumes are tracked on separate accounts
+    if ask_maker.key == bid_maker.key {
+        return Err(EscrowError::SelfTradeNotAllowed.into());
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+
+    if ask_offer.is_expired(now) || bid_offer.is_expired(now) {
+        return Err(EscrowError::OfferExpired.into());
+    }
+
+    // Each maker takes the other offer, and no merkle proof can be supplied on their behalf
+    if !ask_offer.is_taker_allowed(bid_maker.key)
+        || !bid_offer.is_taker_allowed(ask_maker.key)
+        || ask_offer.taker_merkle_root.is_some()
+        || bid_offer.taker_merkle_root.is_some()
+    {
+        return Err(EscrowError::TakerNotAllowed.into());
+    }
+
+    let pair_fee_config = load_optional_account::<PairFeeConfig>(pair_fee_config)?;
+    let mut ask_maker_stats = load_optional_account::<TraderStats>(ask_maker_trader_stats)?;
+    let mut bid_maker_stats = load_optional_account::<TraderStats>(bid_maker_trader_stats)?;
+
+    let (token_a_amount, ask_token_b_amount, bid_token_b_amount) =
+        ask_offer.get_match_amounts(bid_offer, now)?;
+
+    let ask_is_resting = ask_offer.created_at <= bid_offer.created_at;
+
+    let (token_b_amount, token_b_fee_amount, token_a_fee_amount) = if ask_is_resting {
+        (
+            ask_token_b_amount,
+            escrow_state.get_maker_fee(
+                ask_token_b_amount,
+                pair_fee_config.as_ref(),
+                token_b_mint_config.as_ref(),
+                ask_maker_stats.as_ref(),
+            )?,
+            escrow_state.get_taker_fee(
+                token_a_amount,
+                pair_fee_config.as_ref(),
+                token_a_mint_config.as_ref(),
+                bid_maker_stats.as_ref(),
+            )?,
+        )
+    } else {
+        (
+            bid_token_b_amount,
+            escrow_state.get_taker_fee(
+                bid_token_b_amount,
+                pair_fee_config.as_ref(),
+                token_b_mint_config.as_ref(),
+                ask_maker_stats.as_ref(),
+            )?,
+            escrow_state.get_maker_fee(
+                token_a_amount,
+                pair_fee_config.as_ref(),
+                token_a_mint_config.as_ref(),
+                bid_maker_stats.as_ref(),
+            )?,
+        )
+    };
+
+    let surplus_amount = bid_token_b_amount
+        .checked_sub(token_b_amount)
+        .ok_or(EscrowError::MathError)?;
+    let surplus_fee_amount = escrow_state.get_taker_fee(
+        surplus_amount,
+        pair_fee_config.as_ref(),
+        token_b_mint_config.as_ref(),
+        None,
+    )?;
+    let surplus_after_fee = surplus_amount
+        .checked_sub(surplus_fee_amount)
+        .ok_or(EscrowError::MathError)?;
+
+    let token_a_to_transfer_after_fee = token_a_amount
+        .checked_sub(token_a_fee_amount)
+        .ok_or(EscrowError::MathError)?;
+    let token_b_to_transfer_after_fee = token_b_amount
+        .checked_sub(token_b_fee_amount)
+        .ok_or(EscrowError::MathError)?;
+
+    let total_token_b_fee_amount = token_b_fee_amount
+        .checked_add(surplus_fee_amount)
+        .ok_or(EscrowError::MathError)?;
+
+    let ask_offer_signer_seeds = &[
+        Offer::SEED_PREFIX,
+        ask_maker.key.as_ref(),
+        &ask_offer.id.to_le_bytes(),
+        &[ask_offer.bump],
+    ];
+    let bid_offer_signer_seeds = &[
+        Offer::SEED_PREFIX,
+        bid_maker.key.as_ref(),
+        &bid_offer.id.to_le_bytes(),
+        &[bid_offer.bump],
+    ];
+
+    if surplus_after_fee != 0 {
+        transfer_token(
+            token_b_program.to_account_info().clone(),
+            bid_token_b_vault_account.to_account_info().clone(),
+            token_b_mint_account,
+            cranker_token_b_account.to_account_info().clone(),
+            bid_offer.to_account_info().clone(),
+            surplus_after_fee,
+            Some(bid_offer_signer_seeds),
+        )?;
+    }
+
+    // The cranker can be the ask maker, in which case the surplus lands in the same token B
+    // account, so its balance is read once the surplus has been paid
+    ask_maker_token_b_account.reload()?;
+
+    let bid_maker_amount_a_before_transfer = bid_maker_token_a_account.amount;
+    let ask_maker_amount_b_before_transfer = ask_maker_token_b_account.amount;
+
+    transfer_token(
+        token_a_program.to_account_info().clone(),
+        ask_token_a_vault_account.to_account_info().clone(),
+        token_a_mint_account,
+        escrow_token_a_fee_account.to_account_info().clone(),
+        ask_offer.to_account_info().clone(),
+        token_a_fee_amount,
+        Some(ask_offer_signer_seeds),
+    )?;
+
+    transfer_token(
+        token_a_program.to_account_info().clone(),
+        ask_token_a_vault_account.to_account_info().clone(),
+        token_a_mint_account,
+        bid_maker_token_a_account.to_account_info().clone(),
+        ask_offer.to_account_info().clone(),
+        token_a_to_transfer_after_fee,
+        Some(ask_offer_signer_seeds),
+    )?;
+
+    transfer_token(
+        token_b_program.to_account_info().clone(),
+        bid_token_b_vault_account.to_account_info().clone(),
+        token_b_mint_account,
+        escrow_token_b_fee_account.to_account_info().clone(),
+        bid_offer.to_account_info().clone(),
+        total_token_b_fee_amount,
+        Some(bid_offer_signer_seeds),
+    )?;
+
+    transfer_token(
+        token_b_program.to_account_info().clone(),
+        bid_token_b_vault_account.to_account_info().clone(),
+        token_b_mint_account,
+        ask_maker_token_b_account.to_account_info().clone(),
+        bid_offer.to_account_info().clone(),
+        token_b_to_transfer_after_fee,
+        Some(bid_offer_signer_seeds),
+    )?;
+
+    bid_maker_token_a_account.reload()?;
+    ask_maker_token_b_account.reload()?;
+
+    assert_received_net(
+        "bid maker token A",
+        token_a_mint_account,
+        bid_maker_token_a_account.amount,
+        bid_maker_amount_a_before_transfer,
+        token_a_to_transfer_after_fee,
+    )?;
+    assert_received_net(
+        "ask maker token B",
+        token_b_mint_account,
+        ask_maker_token_b_account.amount,
+        ask_maker_amount_b_before_transfer,
+        token_b_to_transfer_after_fee,
+    )?;
+
+    ask_offer.record_fill(token_a_amount, ask_token_b_amount)?;
+    bid_offer.record_fill(token_a_amount, bid_token_b_amount)?;
+
+    let (resting_maker_stats, incoming_maker_stats) = if ask_is_resting {
+        (ask_maker_stats.as_mut(), bid_maker_stats.as_mut())
+    } else {
+        (bid_maker_stats.as_mut(), ask_maker_stats.as_mut())
+    };
+
+    TraderStats::record_fill(
+        pair_fee_config.as_ref(),
+        token_a_amount,
+        token_a_mint_account.decimals,
+        resting_maker_stats,
+        incoming_maker_stats,
+    );
+
+    store_optional_account(ask_maker_trader_stats, ask_maker_stats.as_ref())?;
+    store_optional_account(bid_maker_trader_stats, bid_maker_stats.as_ref())?;
+
+    msg!(
+        "Offers matched : {} token A for {} token B, {} token B surplus",
+        token_a_amount,
+        token_b_amount,
+        surplus_amount
+    );
+
+    emit_cpi!(OffersMatched {
+        ask: ask_offer.key(),
+        bid: bid_offer.key(),
+        cranker: cranker.key(),
+        token_a_amount,
+        token_b_amount,
+        token_a_fee_amount,
+        token_b_fee_amount: total_token_b_fee_amount,
+        surplus_amount,
+    });
+
+    // Filled offers and their vaults are closed back to the accounts which funded them
+    if ask_offer.is_filled()? {
+        ask_token_a_vault_account.reload()?;
+
+        // Anything sent to a vault outside of its offer goes to the counterparty, so the
+        // vault can always be closed
+        if ask_token_a_vault_account.amount != 0 {
+            transfer_token(
+                token_a_program.to_account_info().clone(),
+                ask_token_a_vault_account.to_account_info().clone(),
+                token_a_mint_account,
+                bid_maker_token_a_account.to_account_info().clone(),
+                ask_offer.to_account_info().clone(),
+                ask_token_a_vault_account.amount,
+                Some(ask_offer_signer_seeds),
+            )?;
+        }
+
+        harvest_withheld_fees(
+            token_a_program.to_account_info().clone(),
+            token_a_mint_account,
+            ask_token_a_vault_account.to_account_info().clone(),
+        )?;
+
+        close_token_account(
+            token_a_program.to_account_info().clone(),
+            ask_token_a_vault_account.to_account_info().clone(),
+            ask_offer.to_account_info().clone(),
+            ask_funder.to_account_info().clone(),
+            Some(ask_offer_signer_seeds),
+        )?;
+
+        ask_offer.close(ask_funder.to_account_info())?;
+    }
+
+    if bid_offer.is_filled()? {
+        bid_token_b_vault_account.reload()?;
+
+        if bid_token_b_vault_account.amount != 0 {
+            transfer_token(
+                token_b_program.to_account_info().clone(),
+                bid_token_b_vault_account.to_account_info().clone(),
+                token_b_mint_account,
+                ask_maker_token_b_account.to_account_info().clone(),
+                bid_offer.to_account_info().clone(),
+                bid_token_b_vault_account.amount,
+                Some(bid_offer_signer_seeds),
+            )?;
+        }
+
+        harvest_withheld_fees(
+            token_b_program.to_account_info().clone(),
+            token_b_mint_account,
+            bid_token_b_vault_account.to_account_info().clone(),
+        )?;
+
+        close_token_account(
+            token_b_program.to_account_info().clone(),
+            bid_token_b_vault_account.to_account_info().clone(),
+            bid_offer.to_account_info().clone(),
+            bid_funder.to_account_info().clone(),
+            Some(bid_offer_signer_seeds),
+        )?;
+
+        bid_offer.close(bid_funder.to_account_info())?;
+    }
+
+    Ok(())
+}
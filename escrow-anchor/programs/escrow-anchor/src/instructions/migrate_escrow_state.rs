@@ -0,0 +1,63 @@
+use crate::errors::EscrowError;
+use crate::state::EscrowState;
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{transfer, Transfer};
+
+#[derive(Accounts)]
+pub struct MigrateEscrowState<'info> {
+    /// CHECK: Escrow state written with an older layout, which `Account` cannot deserialize
+    /// Owner, discriminator and manager are checked in the handler
+    #[account(mut, seeds = [EscrowState::SEED], bump)]
+    pub escrow_state: UncheckedAccount<'info>,
+    pub escrow_manager: Signer<'info>,
+    #[account(mut)]
+    pub funding_account: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Grow an escrow state written with an older layout to the current `EscrowState` size.
+///
+/// Fields are only ever appended to `EscrowState`, so the existing bytes keep their
+/// meaning and the zeroed tail deserializes as the default of every new field.
+pub fn handler(ctx: Context<MigrateEscrowState>) -> Result<()> {
+    let MigrateEscrowState {
+        escrow_state,
+        escrow_manager,
+        funding_account,
+        system_program,
+    } = ctx.accounts;
+
+    if *escrow_state.owner != crate::ID {
+        return Err(EscrowError::EscrowStateKeyMismatch.into());
+    }
+
+    EscrowState::assert_is_migratable(&escrow_state.try_borrow_data()?, escrow_manager.key)?;
+
+    let current_len = 8 + EscrowState::INIT_SPACE;
+
+    let rent_top_up = Rent::get()?
+        .minimum_balance(current_len)
+        .saturating_sub(escrow_state.lamports());
+
+    if rent_top_up != 0 {
+        transfer(
+            CpiContext::new(
+                system_program.to_account_info().clone(),
+                Transfer {
+                    from: funding_account.to_account_info().clone(),
+                    to: escrow_state.to_account_info().clone(),
+                },
+            ),
+            rent_top_up,
+        )?;
+    }
+
+    escrow_state.realloc(current_len, true)?;
+
+    let escrow_state_info =
+        EscrowState::try_deserialize(&mut &escrow_state.try_borrow_data()?[..])?;
+
+    msg!("Migrated escrow state: {:?}", escrow_state_info);
+
+    Ok(())
+}
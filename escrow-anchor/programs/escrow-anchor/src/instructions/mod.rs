@@ -1,15 +1,83 @@
+#![allow(ambiguous_glob_reexports)]
+
+pub mod accept_manager;
+pub mod cancel_basket_offer;
+pub mod cancel_bid;
+pub mod cancel_manager_transfer;
 pub mod cancel_offer;
+pub mod close_fee_split_config;
+pub mod close_pair_fee_config;
 pub mod collect_fee;
+pub mod create_auction;
+pub mod create_pair_fee_config;
+pub mod create_trader_stats;
+pub mod distribute_fees;
 pub mod initialize;
+pub mod make_basket_offer;
+pub mod make_bid;
 pub mod make_offer;
+pub mod match_offers;
+pub mod migrate_escrow_state;
+pub mod place_bid;
+pub mod reclaim_expired_basket_offer;
+pub mod reclaim_expired_bid;
+pub mod reclaim_expired_offer;
+pub mod register_referrer;
+pub mod remove_referrer;
+pub mod set_fee_rounding_mode;
+pub mod set_fee_split_config;
+pub mod set_fee_tiers;
 pub mod set_fees;
 pub mod set_manager;
+pub mod set_mint_config;
+pub mod set_mint_list_mode;
+pub mod set_pause;
+pub mod set_referral_share;
+pub mod settle_auction;
+pub mod take_basket_offer;
+pub mod take_bid;
 pub mod take_offer;
+pub mod update_offer;
+pub mod update_pair_fee_config;
+pub mod withdraw_bid_refund;
 
+pub use accept_manager::*;
+pub use cancel_basket_offer::*;
+pub use cancel_bid::*;
+pub use cancel_manager_transfer::*;
 pub use cancel_offer::*;
+pub use close_fee_split_config::*;
+pub use close_pair_fee_config::*;
 pub use collect_fee::*;
+pub use create_auction::*;
+pub use create_pair_fee_config::*;
+pub use create_trader_stats::*;
+pub use distribute_fees::*;
 pub use initialize::*;
+pub use make_basket_offer::*;
+pub use make_bid::*;
 pub use make_offer::*;
+pub use match_offers::*;
+pub use migrate_escrow_state::*;
+pub use place_bid::*;
+pub use reclaim_expired_basket_offer::*;
+pub use reclaim_expired_bid::*;
+pub use reclaim_expired_offer::*;
+pub use register_referrer::*;
+pub use remove_referrer::*;
+pub use set_fee_rounding_mode::*;
+pub use set_fee_split_config::*;
+pub use set_fee_tiers::*;
 pub use set_fees::*;
 pub use set_manager::*;
+pub use set_mint_config::*;
+pub use set_mint_list_mode::*;
+pub use set_pause::*;
+pub use set_referral_share::*;
+pub use settle_auction::*;
+pub use take_basket_offer::*;
+pub use take_bid::*;
 pub use take_offer::*;
+pub use update_offer::*;
+pub use update_pair_fee_config::*;
+pub use withdraw_bid_refund::*;
@@ -0,0 +1,140 @@
+use crate::errors::EscrowError;
+use crate::events::BidPlaced;
+use crate::state::{Auction, BidRefund, EscrowState};
+use crate::utils::{assert_received_net, get_transfer_fee, transfer_token};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug)]
+pub struct PlaceBidArgs {
+    pub bid_amount: u64,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct PlaceBid<'info> {
+    #[account(seeds = [EscrowState::SEED], bump = escrow_state.bump)]
+    pub escrow_state: Box<Account<'info, EscrowState>>,
+    #[account(mut, seeds = [Auction::SEED_PREFIX, auction.maker.as_ref(), auction.id.to_le_bytes().as_ref()], bump = auction.bump)]
+    pub auction: Box<Account<'info, Auction>>,
+    #[account(address = auction.token_mint_b, mint::token_program = token_program)]
+    pub token_b_mint_account: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut, associated_token::mint = token_b_mint_account, associated_token::authority = bidder, associated_token::token_program = token_program)]
+    pub bidder_token_b_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(init_if_needed, payer = funding_account, associated_token::mint = token_b_mint_account, associated_token::authority = auction, associated_token::token_program = token_program)]
+    pub auction_token_b_bid_vault_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    /// Refund of the highest bidder being outbid, required once the auction has a bid
+    #[account(init_if_needed, payer = funding_account, space = 8 + BidRefund::INIT_SPACE, seeds = [BidRefund::SEED_PREFIX, auction.key().as_ref(), auction.highest_bidder.unwrap_or_default().as_ref()], bump)]
+    pub previous_bid_refund: Option<Box<Account<'info, BidRefund>>>,
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+    #[account(mut)]
+    pub funding_account: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Escrow a new highest bid in the bid vault.
+///
+/// The outbid amount stays in the bid vault and is credited to the outbid bidder's refund,
+/// which they withdraw with `withdraw_bid_refund`. Refunds are never pushed to the outbid
+/// bidder, so a bidder cannot block new bids by making its token account reject transfers.
+pub fn handler(ctx: Context<PlaceBid>, args: PlaceBidArgs) -> Result<()> {
+    let PlaceBid {
+        escrow_state,
+        auction,
+        token_b_mint_account,
+        bidder_token_b_account,
+        auction_token_b_bid_vault_account,
+        previous_bid_refund,
+        bidder,
+        funding_account,
+        token_program,
+        ..
+    } = ctx.accounts;
+
+    if escrow_state.take_paused {
+        return Err(EscrowError::TakePaused.into());
+    }
+
+    if auction.is_ended(Clock::get()?.unix_timestamp) {
+        return Err(EscrowError::AuctionEnded.into());
+    }
+
+    if bidder.key() == auction.maker {
+        return Err(EscrowError::MakerCannotBid.into());
+    }
+
+    // Bids are compared on the amount the vault actually receives
+    let bid_received_amount = args
+        .bid_amount
+        .checked_sub(get_transfer_fee(token_b_mint_account, args.bid_amount)?)
+        .ok_or(EscrowError::MathError)?;
+
+    // The highest bid amount is zero until the first bid
+    let outbid_bidder = auction.highest_bidder;
+    let outbid_amount = auction.highest_bid_amount;
+
+    auction.record_bid(bidder.key(), bid_received_amount)?;
+
+    let vault_amount_before_bid = auction_token_b_bid_vault_account.amount;
+
+    transfer_token(
+        token_program.to_account_info().clone(),
+        bidder_token_b_account.to_account_info().clone(),
+        token_b_mint_account,
+        auction_token_b_bid_vault_account.to_account_info().clone(),
+        bidder.to_account_info().clone(),
+        args.bid_amount,
+        None,
+    )?;
+
+    if let Some(outbid_bidder) = outbid_bidder {
+        let previous_bid_refund = previous_bid_refund
+            .as_mut()
+            .ok_or(EscrowError::BidRefundMissing)?;
+
+        // The refund's rent is paid by the bid which creates it and goes back to its funder
+        let refund_funder = (previous_bid_refund.amount == 0).then(|| funding_account.key());
+
+        BidRefund::write(
+            previous_bid_refund,
+            Some(auction.key()),
+            Some(outbid_bidder),
+            refund_funder,
+            ctx.bumps.previous_bid_refund,
+        )?;
+
+        previous_bid_refund.record_refund(outbid_amount)?;
+    }
+
+    auction_token_b_bid_vault_account.reload()?;
+
+    assert_received_net(
+        "bid vault token B",
+        token_b_mint_account,
+        auction_token_b_bid_vault_account.amount,
+        vault_amount_before_bid,
+        args.bid_amount,
+    )?;
+
+    msg!(
+        "Bid placed : {} token B on {}",
+        bid_received_amount,
+        auction.key()
+    );
+
+    emit_cpi!(BidPlaced {
+        auction: auction.key(),
+        bidder: bidder.key(),
+        bid_amount: bid_received_amount,
+        outbid_bidder,
+        outbid_amount,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,129 @@
+use crate::errors::EscrowError;
+use crate::events::BasketOfferCancelled;
+use crate::state::BasketOffer;
+use crate::utils::{
+    assert_associated_token_account, close_token_account, create_associated_token_account,
+    get_token_account_amount, harvest_withheld_fees, load_mint, transfer_token,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token_interface::TokenInterface};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ReclaimExpiredBasketOffer<'info> {
+    #[account(mut, seeds = [BasketOffer::SEED_PREFIX, maker.key().as_ref(), basket_offer.id.to_le_bytes().as_ref()], bump = basket_offer.bump, close = funder)]
+    pub basket_offer: Account<'info, BasketOffer>,
+    /// CHECK : address of maker wallet
+    #[account(address = basket_offer.maker)]
+    pub maker: UncheckedAccount<'info>,
+    /// CHECK : address of the account which funded the basket offer, receives the rent back
+    #[account(mut, address = basket_offer.funder)]
+    pub funder: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub funding_account: Signer<'info>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Return every offered leg of an expired basket offer to its maker. Anyone can crank this.
+///
+/// The remaining accounts are, for each offered leg in order, its mint, its token program,
+/// the basket offer's vault and the maker's ATA for the mint, which is created if missing.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ReclaimExpiredBasketOffer<'info>>,
+) -> Result<()> {
+    let ReclaimExpiredBasketOffer {
+        basket_offer,
+        maker,
+        funder,
+        funding_account,
+        associated_token_program,
+        system_program,
+        ..
+    } = ctx.accounts;
+
+    if !basket_offer.is_expired(Clock::get()?.unix_timestamp) {
+        return Err(EscrowError::OfferNotExpired.into());
+    }
+
+    if ctx.remaining_accounts.len() != basket_offer.offered.len() * 4 {
+        return Err(EscrowError::TokenAccountMissing.into());
+    }
+
+    let basket_offer_signer_seeds = &[
+        BasketOffer::SEED_PREFIX,
+        maker.key.as_ref(),
+        &basket_offer.id.to_le_bytes(),
+        &[basket_offer.bump],
+    ];
+
+    for (leg, accounts) in basket_offer
+        .offered
+        .iter()
+        .zip(ctx.remaining_accounts.chunks(4))
+    {
+        let [mint_account, token_program, vault_account, maker_token_account] = accounts else {
+            return Err(EscrowError::TokenAccountMissing.into());
+        };
+
+        let token_program = Interface::<TokenInterface>::try_from(token_program)?;
+        let mint_account = load_mint(mint_account, &leg.mint, &token_program.key())?;
+
+        assert_associated_token_account(
+            vault_account,
+            &basket_offer.key(),
+            &leg.mint,
+            &token_program.key(),
+        )?;
+        assert_associated_token_account(
+            maker_token_account,
+            maker.key,
+            &leg.mint,
+            &token_program.key(),
+        )?;
+
+        create_associated_token_account(
+            associated_token_program,
+            funding_account.to_account_info().clone(),
+            maker_token_account.clone(),
+            maker.to_account_info().clone(),
+            mint_account.to_account_info().clone(),
+            token_program.to_account_info().clone(),
+            system_program.to_account_info().clone(),
+        )?;
+
+        transfer_token(
+            token_program.to_account_info().clone(),
+            vault_account.clone(),
+            &mint_account,
+            maker_token_account.clone(),
+            basket_offer.to_account_info().clone(),
+            get_token_account_amount(vault_account)?,
+            Some(basket_offer_signer_seeds),
+        )?;
+
+        harvest_withheld_fees(
+            token_program.to_account_info().clone(),
+            &mint_account,
+            vault_account.clone(),
+        )?;
+
+        close_token_account(
+            token_program.to_account_info().clone(),
+            vault_account.clone(),
+            basket_offer.to_account_info().clone(),
+            funder.to_account_info().clone(),
+            Some(basket_offer_signer_seeds),
+        )?;
+    }
+
+    msg!("Expired basket offer reclaimed : {}", basket_offer.key());
+
+    emit_cpi!(BasketOfferCancelled {
+        basket_offer: basket_offer.key(),
+        maker: maker.key(),
+        expired: true,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,116 @@
+use crate::errors::EscrowError;
+use crate::events::OfferCancelled;
+use crate::state::{Offer, OfferSide};
+use crate::utils::{
+    assert_received_net, close_token_account, harvest_withheld_fees, transfer_token,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ReclaimExpiredBid<'info> {
+    #[account(mut, seeds = [Offer::SEED_PREFIX, maker.key().as_ref(), escrow_account.id.to_le_bytes().as_ref()], bump = escrow_account.bump, close = funder)]
+    pub escrow_account: Account<'info, Offer>,
+    #[account(mut, address = escrow_account.token_mint_b, mint::token_program = token_program)]
+    pub token_b_mint_account: InterfaceAccount<'info, Mint>,
+    #[account(init_if_needed, payer = funding_account, associated_token::mint = token_b_mint_account, associated_token::authority = maker, associated_token::token_program = token_program)]
+    pub maker_token_b_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, associated_token::mint = token_b_mint_account, associated_token::authority = escrow_account, associated_token::token_program = token_program)]
+    pub escrow_token_b_vault_account: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK : address of maker wallet
+    #[account(address = escrow_account.maker)]
+    pub maker: UncheckedAccount<'info>,
+    /// CHECK : address of the account which funded the bid, receives the rent back
+    #[account(mut, address = escrow_account.funder)]
+    pub funder: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub funding_account: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Return the token B of an expired bid to its maker. Anyone can crank this.
+///
+/// Native SOL bids are returned to the maker's wSOL account, since unwrapping it would
+/// require the maker's signature.
+pub fn handler(ctx: Context<ReclaimExpiredBid>) -> Result<()> {
+    let ReclaimExpiredBid {
+        escrow_account,
+        token_b_mint_account,
+        maker_token_b_account,
+        escrow_token_b_vault_account,
+        maker,
+        funder,
+        token_program,
+        ..
+    } = ctx.accounts;
+
+    if escrow_account.side != OfferSide::Bid {
+        return Err(EscrowError::OfferSideMismatch.into());
+    }
+
+    if !escrow_account.is_expired(Clock::get()?.unix_timestamp) {
+        return Err(EscrowError::OfferNotExpired.into());
+    }
+
+    let escrow_account_signer_seeds = &[
+        Offer::SEED_PREFIX,
+        maker.key.as_ref(),
+        &escrow_account.id.to_le_bytes(),
+        &[escrow_account.bump],
+    ];
+
+    let vault_amount_b = escrow_token_b_vault_account.amount;
+    let maker_token_b_amount_before_transfer = maker_token_b_account.amount;
+
+    transfer_token(
+        token_program.to_account_info().clone(),
+        escrow_token_b_vault_account.to_account_info().clone(),
+        token_b_mint_account,
+        maker_token_b_account.to_account_info().clone(),
+        escrow_account.to_account_info().clone(),
+        vault_amount_b,
+        Some(escrow_account_signer_seeds),
+    )?;
+
+    maker_token_b_account.reload()?;
+
+    assert_received_net(
+        "maker token B",
+        token_b_mint_account,
+        maker_token_b_account.amount,
+        maker_token_b_amount_before_transfer,
+        vault_amount_b,
+    )?;
+
+    harvest_withheld_fees(
+        token_program.to_account_info().clone(),
+        token_b_mint_account,
+        escrow_token_b_vault_account.to_account_info().clone(),
+    )?;
+
+    close_token_account(
+        token_program.to_account_info().clone(),
+        escrow_token_b_vault_account.to_account_info().clone(),
+        escrow_account.to_account_info().clone(),
+        funder.to_account_info().clone(),
+        Some(escrow_account_signer_seeds),
+    )?;
+
+    msg!("Expired bid reclaimed : {}", escrow_account.key());
+
+    emit_cpi!(OfferCancelled {
+        offer: escrow_account.key(),
+        maker: maker.key(),
+        side: OfferSide::Bid,
+        returned_amount: vault_amount_b,
+        expired: true,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use crate::events::ReferrerRegistered;
+use crate::state::{EscrowState, Referrer};
+use anchor_lang::prelude::*;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RegisterReferrer<'info> {
+    #[account(seeds = [EscrowState::SEED], bump = escrow_state.bump)]
+    pub escrow_state: Account<'info, EscrowState>,
+    #[account(mut, address = escrow_state.manager)]
+    pub escrow_manager: Signer<'info>,
+    /// CHECK : wallet of the referrer, which can then be paid referral shares
+    pub referrer_wallet: UncheckedAccount<'info>,
+    #[account(init, payer = escrow_manager, space = 8 + Referrer::INIT_SPACE, seeds = [Referrer::SEED_PREFIX, referrer_wallet.key().as_ref()], bump)]
+    pub referrer: Account<'info, Referrer>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Register a wallet which takers can name as the referrer of their trades.
+pub fn handler(ctx: Context<RegisterReferrer>) -> Result<()> {
+    let RegisterReferrer {
+        referrer_wallet,
+        referrer,
+        ..
+    } = ctx.accounts;
+
+    let referrer_info = Referrer::write(
+        referrer,
+        Some(referrer_wallet.key()),
+        Some(ctx.bumps.referrer),
+    )?;
+
+    msg!("Registered referrer : {:?}", referrer_info);
+
+    emit_cpi!(ReferrerRegistered {
+        referrer: referrer_info.wallet,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,27 @@
+use crate::events::ReferrerRemoved;
+use crate::state::{EscrowState, Referrer};
+use anchor_lang::prelude::*;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RemoveReferrer<'info> {
+    #[account(seeds = [EscrowState::SEED], bump = escrow_state.bump)]
+    pub escrow_state: Account<'info, EscrowState>,
+    #[account(mut, address = escrow_state.manager)]
+    pub escrow_manager: Signer<'info>,
+    #[account(mut, seeds = [Referrer::SEED_PREFIX, referrer.wallet.as_ref()], bump = referrer.bump, close = escrow_manager)]
+    pub referrer: Account<'info, Referrer>,
+}
+
+/// Remove a referrer, so takers can no longer name it on their trades.
+pub fn handler(ctx: Context<RemoveReferrer>) -> Result<()> {
+    let referrer_wallet = ctx.accounts.referrer.wallet;
+
+    msg!("Removed referrer : {}", referrer_wallet);
+
+    emit_cpi!(ReferrerRemoved {
+        referrer: referrer_wallet,
+    });
+
+    Ok(())
+}
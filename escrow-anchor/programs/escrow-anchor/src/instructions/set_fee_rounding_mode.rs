@@ -0,0 +1,41 @@
+use crate::errors::EscrowError;
+use crate::events::FeeRoundingModeUpdated;
+use crate::state::{EscrowState, FeeRoundingMode};
+use anchor_lang::prelude::*;
+
+#[derive(AnchorDeserialize, AnchorSerialize, Debug, Default)]
+pub struct SetFeeRoundingModeArgs {
+    pub fee_rounding_mode: FeeRoundingMode,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetFeeRoundingMode<'info> {
+    #[account(mut, seeds = [EscrowState::SEED], bump = escrow_state.bump)]
+    pub escrow_state: Account<'info, EscrowState>,
+    pub escrow_manager: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<SetFeeRoundingMode>, args: SetFeeRoundingModeArgs) -> Result<()> {
+    let SetFeeRoundingMode {
+        escrow_state,
+        escrow_manager,
+        ..
+    } = ctx.accounts;
+
+    if escrow_manager.key() != escrow_state.manager {
+        return Err(EscrowError::ManagerKeyUnauthorized.into());
+    }
+
+    escrow_state.fee_rounding_mode = args.fee_rounding_mode;
+
+    let escrow_state_info = EscrowState::write(escrow_state, None, None, None, None)?;
+
+    msg!("Updated escrow fee rounding mode : {:?}", escrow_state_info);
+
+    emit_cpi!(FeeRoundingModeUpdated {
+        fee_rounding_mode: escrow_state.fee_rounding_mode,
+    });
+
+    Ok(())
+}
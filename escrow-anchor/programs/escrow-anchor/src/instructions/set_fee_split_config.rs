@@ -0,0 +1,42 @@
+use crate::events::FeeSplitConfigUpdated;
+use crate::state::{EscrowState, FeeSplitConfig, FeeSplitRecipient};
+use anchor_lang::prelude::*;
+
+#[derive(AnchorDeserialize, AnchorSerialize, Debug, Default)]
+pub struct SetFeeSplitConfigArgs {
+    pub recipients: Vec<FeeSplitRecipient>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetFeeSplitConfig<'info> {
+    #[account(seeds = [EscrowState::SEED], bump = escrow_state.bump)]
+    pub escrow_state: Account<'info, EscrowState>,
+    #[account(mut, address = escrow_state.manager)]
+    pub escrow_manager: Signer<'info>,
+    #[account(init_if_needed, payer = escrow_manager, space = 8 + FeeSplitConfig::INIT_SPACE, seeds = [FeeSplitConfig::SEED], bump)]
+    pub fee_split_config: Account<'info, FeeSplitConfig>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<SetFeeSplitConfig>, args: SetFeeSplitConfigArgs) -> Result<()> {
+    FeeSplitConfig::assert_is_valid_split(&args.recipients)?;
+
+    let SetFeeSplitConfig {
+        fee_split_config, ..
+    } = ctx.accounts;
+
+    let fee_split_config_info = FeeSplitConfig::write(
+        fee_split_config,
+        Some(args.recipients),
+        Some(ctx.bumps.fee_split_config),
+    )?;
+
+    msg!("Updated fee split config : {:?}", fee_split_config_info);
+
+    emit_cpi!(FeeSplitConfigUpdated {
+        recipients: fee_split_config_info.recipients,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use crate::errors::EscrowError;
+use crate::events::FeeTiersUpdated;
+use crate::state::{EscrowState, FeeTier};
+use crate::utils::assert_is_bps_in_range;
+use anchor_lang::prelude::*;
+
+#[derive(AnchorDeserialize, AnchorSerialize, Debug, Default)]
+pub struct SetFeeTiersArgs {
+    pub fee_tiers: Vec<FeeTier>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetFeeTiers<'info> {
+    #[account(mut, seeds = [EscrowState::SEED], bump = escrow_state.bump)]
+    pub escrow_state: Account<'info, EscrowState>,
+    pub escrow_manager: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<SetFeeTiers>, args: SetFeeTiersArgs) -> Result<()> {
+    if args.fee_tiers.len() > EscrowState::MAX_FEE_TIERS {
+        return Err(EscrowError::TooManyFeeTiers.into());
+    }
+
+    for fee_tier in args.fee_tiers.iter() {
+        assert_is_bps_in_range(fee_tier.fee_discount_bps)?;
+    }
+
+    let SetFeeTiers {
+        escrow_state,
+        escrow_manager,
+        ..
+    } = ctx.accounts;
+
+    if escrow_manager.key() != escrow_state.manager {
+        return Err(EscrowError::ManagerKeyUnauthorized.into());
+    }
+
+    escrow_state.fee_tiers = args.fee_tiers;
+
+    let escrow_state_info = EscrowState::write(escrow_state, None, None, None, None)?;
+
+    msg!("Updated escrow fee tiers : {:?}", escrow_state_info);
+
+    emit_cpi!(FeeTiersUpdated {
+        fee_tiers: escrow_state.fee_tiers.clone(),
+    });
+
+    Ok(())
+}
@@ -1,4 +1,5 @@
 use crate::errors::EscrowError;
+use crate::events::FeesUpdated;
 use crate::state::EscrowState;
 use crate::utils::assert_is_bps_in_range;
 use anchor_lang::prelude::*;
@@ -9,6 +10,7 @@ pub struct SetFeesArgs {
     pub taker_fee_bps: u16,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct SetFees<'info> {
     #[account(mut, seeds = [EscrowState::SEED], bump = escrow_state.bump)]
@@ -23,6 +25,7 @@ pub fn handler(ctx: Context<SetFees>, args: SetFeesArgs) -> Result<()> {
     let SetFees {
         escrow_state,
         escrow_manager,
+        ..
     } = ctx.accounts;
 
     if escrow_manager.key() != escrow_state.manager {
@@ -39,5 +42,12 @@ pub fn handler(ctx: Context<SetFees>, args: SetFeesArgs) -> Result<()> {
 
     msg!("Updated escrow fee : {:?}", escrow_state_info);
 
+    emit_cpi!(FeesUpdated {
+        token_mint_a: None,
+        token_mint_b: None,
+        maker_fee_bps: escrow_state_info.maker_fee_bps,
+        taker_fee_bps: escrow_state_info.taker_fee_bps,
+    });
+
     Ok(())
 }
@@ -1,14 +1,16 @@
 use crate::errors::EscrowError;
+use crate::events::ManagerTransferProposed;
 use crate::state::EscrowState;
 use anchor_lang::prelude::*;
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct SetManager<'info> {
     #[account(mut)]
     pub escrow_state: Account<'info, EscrowState>,
     pub escrow_manager: Signer<'info>,
-    /// CHECK: This is new manager of Escrow Account
-    /// Anchor does not sanity check on this account
+    /// CHECK: This is the proposed new manager of Escrow Account
+    /// It only becomes manager once it signs `accept_manager`
     pub new_manager: UncheckedAccount<'info>,
 }
 
@@ -17,6 +19,7 @@ pub fn handler(ctx: Context<SetManager>) -> Result<()> {
         escrow_state,
         escrow_manager,
         new_manager,
+        ..
     } = ctx.accounts;
 
     if escrow_manager.key() != escrow_state.manager {
@@ -27,10 +30,16 @@ pub fn handler(ctx: Context<SetManager>) -> Result<()> {
         return Err(EscrowError::ManagerKeyAlreadySet.into());
     }
 
-    let escrow_state_info =
-        EscrowState::write(escrow_state, Some(new_manager.key()), None, None, None)?;
+    escrow_state.pending_manager = Some(new_manager.key());
 
-    msg!("Updated escrow manager: {:?}", escrow_state_info);
+    let escrow_state_info = EscrowState::write(escrow_state, None, None, None, None)?;
+
+    msg!("Proposed escrow manager: {:?}", escrow_state_info);
+
+    emit_cpi!(ManagerTransferProposed {
+        manager: escrow_state.manager,
+        pending_manager: new_manager.key(),
+    });
 
     Ok(())
 }
@@ -0,0 +1,59 @@
+use crate::errors::EscrowError;
+use crate::events::MintConfigUpdated;
+use crate::state::{EscrowState, MintConfig};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+#[derive(AnchorDeserialize, AnchorSerialize, Debug, Default)]
+pub struct SetMintConfigArgs {
+    pub allowed: bool,
+    pub denied: bool,
+    pub min_fee_amount: u64,
+    pub notes: String,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetMintConfig<'info> {
+    #[account(seeds = [EscrowState::SEED], bump = escrow_state.bump)]
+    pub escrow_state: Account<'info, EscrowState>,
+    #[account(mut, address = escrow_state.manager)]
+    pub escrow_manager: Signer<'info>,
+    pub token_mint_account: InterfaceAccount<'info, Mint>,
+    #[account(init_if_needed, payer = escrow_manager, space = 8 + MintConfig::INIT_SPACE, seeds = [MintConfig::SEED_PREFIX, token_mint_account.key().as_ref()], bump)]
+    pub mint_config: Account<'info, MintConfig>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<SetMintConfig>, args: SetMintConfigArgs) -> Result<()> {
+    if args.notes.len() > MintConfig::MAX_NOTES_LEN {
+        return Err(EscrowError::NotesTooLong.into());
+    }
+
+    let SetMintConfig {
+        token_mint_account,
+        mint_config,
+        ..
+    } = ctx.accounts;
+
+    let mint_config_info = MintConfig::write(
+        mint_config,
+        Some(token_mint_account.key()),
+        Some(args.allowed),
+        Some(args.denied),
+        Some(args.min_fee_amount),
+        Some(args.notes),
+        Some(ctx.bumps.mint_config),
+    )?;
+
+    msg!("Updated mint config : {:?}", mint_config_info);
+
+    emit_cpi!(MintConfigUpdated {
+        mint: mint_config_info.mint,
+        allowed: mint_config_info.allowed,
+        denied: mint_config_info.denied,
+        min_fee_amount: mint_config_info.min_fee_amount,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use crate::errors::EscrowError;
+use crate::events::MintListModeUpdated;
+use crate::state::{EscrowState, MintListMode};
+use anchor_lang::prelude::*;
+
+#[derive(AnchorDeserialize, AnchorSerialize, Debug, Default)]
+pub struct SetMintListModeArgs {
+    pub mint_list_mode: MintListMode,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetMintListMode<'info> {
+    #[account(mut, seeds = [EscrowState::SEED], bump = escrow_state.bump)]
+    pub escrow_state: Account<'info, EscrowState>,
+    pub escrow_manager: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<SetMintListMode>, args: SetMintListModeArgs) -> Result<()> {
+    let SetMintListMode {
+        escrow_state,
+        escrow_manager,
+        ..
+    } = ctx.accounts;
+
+    if escrow_manager.key() != escrow_state.manager {
+        return Err(EscrowError::ManagerKeyUnauthorized.into());
+    }
+
+    escrow_state.mint_list_mode = args.mint_list_mode;
+
+    let escrow_state_info = EscrowState::write(escrow_state, None, None, None, None)?;
+
+    msg!("Updated escrow mint list mode : {:?}", escrow_state_info);
+
+    emit_cpi!(MintListModeUpdated {
+        mint_list_mode: escrow_state.mint_list_mode,
+    });
+
+    Ok(())
+}
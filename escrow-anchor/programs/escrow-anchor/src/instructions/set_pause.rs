@@ -0,0 +1,48 @@
+use crate::errors::EscrowError;
+use crate::events::PauseUpdated;
+use crate::state::EscrowState;
+use anchor_lang::prelude::*;
+
+#[derive(AnchorDeserialize, AnchorSerialize, Debug, Default)]
+pub struct SetPauseArgs {
+    pub make_paused: bool,
+    pub take_paused: bool,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetPause<'info> {
+    #[account(mut, seeds = [EscrowState::SEED], bump = escrow_state.bump)]
+    pub escrow_state: Account<'info, EscrowState>,
+    pub escrow_manager: Signer<'info>,
+}
+
+/// Pause or resume making and taking offers.
+///
+/// There is no cancel pause: cancelling and reclaiming offers, bids and baskets are never
+/// paused, so makers can always exit.
+pub fn handler(ctx: Context<SetPause>, args: SetPauseArgs) -> Result<()> {
+    let SetPause {
+        escrow_state,
+        escrow_manager,
+        ..
+    } = ctx.accounts;
+
+    if escrow_manager.key() != escrow_state.manager {
+        return Err(EscrowError::ManagerKeyUnauthorized.into());
+    }
+
+    escrow_state.make_paused = args.make_paused;
+    escrow_state.take_paused = args.take_paused;
+
+    let escrow_state_info = EscrowState::write(escrow_state, None, None, None, None)?;
+
+    msg!("Updated escrow pause flags : {:?}", escrow_state_info);
+
+    emit_cpi!(PauseUpdated {
+        make_paused: escrow_state.make_paused,
+        take_paused: escrow_state.take_paused,
+    });
+
+    Ok(())
+}
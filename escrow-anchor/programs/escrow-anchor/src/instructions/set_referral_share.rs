@@ -0,0 +1,44 @@
+use crate::errors::EscrowError;
+use crate::events::ReferralShareUpdated;
+use crate::state::EscrowState;
+use crate::utils::assert_is_bps_in_range;
+use anchor_lang::prelude::*;
+
+#[derive(AnchorDeserialize, AnchorSerialize, Debug, Default)]
+pub struct SetReferralShareArgs {
+    pub referral_share_bps: u16,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetReferralShare<'info> {
+    #[account(mut, seeds = [EscrowState::SEED], bump = escrow_state.bump)]
+    pub escrow_state: Account<'info, EscrowState>,
+    pub escrow_manager: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<SetReferralShare>, args: SetReferralShareArgs) -> Result<()> {
+    assert_is_bps_in_range(args.referral_share_bps)?;
+
+    let SetReferralShare {
+        escrow_state,
+        escrow_manager,
+        ..
+    } = ctx.accounts;
+
+    if escrow_manager.key() != escrow_state.manager {
+        return Err(EscrowError::ManagerKeyUnauthorized.into());
+    }
+
+    escrow_state.referral_share_bps = args.referral_share_bps;
+
+    let escrow_state_info = EscrowState::write(escrow_state, None, None, None, None)?;
+
+    msg!("Updated escrow referral share : {:?}", escrow_state_info);
+
+    emit_cpi!(ReferralShareUpdated {
+        referral_share_bps: escrow_state.referral_share_bps,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,252 @@
+use crate::errors::EscrowError;
+use crate::events::AuctionSettled;
+use crate::state::{Auction, EscrowState, MintConfig, PairFeeConfig};
+use crate::utils::{
+    assert_received_net, close_token_account, harvest_withheld_fees, load_optional_account,
+    transfer_token,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SettleAuction<'info> {
+    #[account(seeds = [EscrowState::SEED], bump = escrow_state.bump)]
+    pub escrow_state: Box<Account<'info, EscrowState>>,
+    #[account(mut, seeds = [Auction::SEED_PREFIX, maker.key().as_ref(), auction.id.to_le_bytes().as_ref()], bump = auction.bump)]
+    pub auction: Box<Account<'info, Auction>>,
+    #[account(mut, address = auction.token_mint_a, mint::token_program = token_a_program)]
+    pub token_a_mint_account: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut, address = auction.token_mint_b, mint::token_program = token_b_program)]
+    pub token_b_mint_account: Box<InterfaceAccount<'info, Mint>>,
+    /// CHECK : mint config PDA of token B, which is empty unless the manager created it
+    #[account(seeds = [MintConfig::SEED_PREFIX, token_b_mint_account.key().as_ref()], bump)]
+    pub token_b_mint_config: UncheckedAccount<'info>,
+    /// CHECK : fee override PDA of the trading pair, which is empty unless the manager created it
+    #[account(seeds = [PairFeeConfig::SEED_PREFIX, token_a_mint_account.key().as_ref(), token_b_mint_account.key().as_ref()], bump)]
+    pub pair_fee_config: UncheckedAccount<'info>,
+    #[account(mut, associated_token::mint = token_a_mint_account, associated_token::authority = auction, associated_token::token_program = token_a_program)]
+    pub auction_token_a_vault_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, associated_token::mint = token_b_mint_account, associated_token::authority = auction, associated_token::token_program = token_b_program)]
+    pub auction_token_b_bid_vault_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    /// CHECK : wallet of the winning bidder, checked against the auction in the handler
+    pub winner: Option<UncheckedAccount<'info>>,
+    #[account(init_if_needed, payer = funding_account, associated_token::mint = token_a_mint_account, associated_token::authority = winner, associated_token::token_program = token_a_program)]
+    pub winner_token_a_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    #[account(init_if_needed, payer = funding_account, associated_token::mint = token_a_mint_account, associated_token::authority = maker, associated_token::token_program = token_a_program)]
+    pub maker_token_a_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    #[account(init_if_needed, payer = funding_account, associated_token::mint = token_b_mint_account, associated_token::authority = maker, associated_token::token_program = token_b_program)]
+    pub maker_token_b_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    #[account(init_if_needed, payer = funding_account, associated_token::mint = token_b_mint_account, associated_token::authority = escrow_state, associated_token::token_program = token_b_program)]
+    pub escrow_token_b_fee_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    /// CHECK : address of maker wallet
+    #[account(address = auction.maker)]
+    pub maker: UncheckedAccount<'info>,
+    /// CHECK : address of the account which funded the auction, receives the rent back
+    #[account(mut, address = auction.funder)]
+    pub funder: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub funding_account: Signer<'info>,
+    pub token_a_program: Interface<'info, TokenInterface>,
+    pub token_b_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Settle an ended auction. Anyone can crank this.
+///
+/// The winner receives token A and the maker receives the winning bid minus the maker fee.
+/// Without bids token A goes back to the maker. The vaults' rent goes to the auction funder.
+///
+/// Refunds of outbid bidders stay in the bid vault, so while any are pending the auction
+/// is only marked settled and the last `withdraw_bid_refund` closes it.
+pub fn handler(ctx: Context<SettleAuction>) -> Result<()> {
+    let SettleAuction {
+        escrow_state,
+        auction,
+        token_a_mint_account,
+        token_b_mint_account,
+        token_b_mint_config,
+        pair_fee_config,
+        auction_token_a_vault_account,
+        auction_token_b_bid_vault_account,
+        winner,
+        winner_token_a_account,
+        maker_token_a_account,
+        maker_token_b_account,
+        escrow_token_b_fee_account,
+        maker,
+        funder,
+        token_a_program,
+        token_b_program,
+        ..
+    } = ctx.accounts;
+
+    if !auction.is_ended(Clock::get()?.unix_timestamp) {
+        return Err(EscrowError::AuctionNotEnded.into());
+    }
+
+    if auction.settled {
+        return Err(EscrowError::AuctionAlreadySettled.into());
+    }
+
+    let auction_signer_seeds = &[
+        Auction::SEED_PREFIX,
+        maker.key.as_ref(),
+        &auction.id.to_le_bytes(),
+        &[auction.bump],
+    ];
+
+    let token_a_amount = auction_token_a_vault_account.amount;
+    let mut token_b_fee_amount = 0;
+
+    // Token A goes to the winner, or back to the maker if nobody bid
+    let token_a_recipient_account = match auction.highest_bidder {
+        Some(highest_bidder) => {
+            if winner.as_ref().map(|winner| winner.key()) != Some(highest_bidder) {
+                return Err(EscrowError::BidderKeyMismatch.into());
+            }
+
+            let auction_token_b_bid_vault_account = auction_token_b_bid_vault_account
+                .as_ref()
+                .ok_or(EscrowError::TokenAccountMissing)?;
+            let maker_token_b_account = maker_token_b_account
+                .as_mut()
+                .ok_or(EscrowError::TokenAccountMissing)?;
+            let escrow_token_b_fee_account = escrow_token_b_fee_account
+                .as_ref()
+                .ok_or(EscrowError::TokenAccountMissing)?;
+
+            let token_b_amount = auction.highest_bid_amount;
+
+            token_b_fee_amount = escrow_state.get_token_b_fee(
+                token_b_amount,
+                load_optional_account::<PairFeeConfig>(pair_fee_config)?.as_ref(),
+                load_optional_account::<MintConfig>(token_b_mint_config)?.as_ref(),
+                None,
+            )?;
+
+            // Anything sent to the bid vault outside of bids also goes to the maker, so the
+            // vault can always be closed once the refunds are withdrawn
+            let token_b_to_transfer_after_fee = auction_token_b_bid_vault_account
+                .amount
+                .checked_sub(auction.pending_refund_amount)
+                .ok_or(EscrowError::MathError)?
+                .checked_sub(token_b_fee_amount)
+                .ok_or(EscrowError::MathError)?;
+
+            let maker_amount_b_before_transfer = maker_token_b_account.amount;
+
+            transfer_token(
+                token_b_program.to_account_info().clone(),
+                auction_token_b_bid_vault_account.to_account_info().clone(),
+                token_b_mint_account,
+                escrow_token_b_fee_account.to_account_info().clone(),
+                auction.to_account_info().clone(),
+                token_b_fee_amount,
+                Some(auction_signer_seeds),
+            )?;
+
+            transfer_token(
+                token_b_program.to_account_info().clone(),
+                auction_token_b_bid_vault_account.to_account_info().clone(),
+                token_b_mint_account,
+                maker_token_b_account.to_account_info().clone(),
+                auction.to_account_info().clone(),
+                token_b_to_transfer_after_fee,
+                Some(auction_signer_seeds),
+            )?;
+
+            maker_token_b_account.reload()?;
+
+            assert_received_net(
+                "maker token B",
+                token_b_mint_account,
+                maker_token_b_account.amount,
+                maker_amount_b_before_transfer,
+                token_b_to_transfer_after_fee,
+            )?;
+
+            if auction.pending_refund_amount == 0 {
+                harvest_withheld_fees(
+                    token_b_program.to_account_info().clone(),
+                    token_b_mint_account,
+                    auction_token_b_bid_vault_account.to_account_info().clone(),
+                )?;
+
+                close_token_account(
+                    token_b_program.to_account_info().clone(),
+                    auction_token_b_bid_vault_account.to_account_info().clone(),
+                    auction.to_account_info().clone(),
+                    funder.to_account_info().clone(),
+                    Some(auction_signer_seeds),
+                )?;
+            }
+
+            winner_token_a_account
+                .as_mut()
+                .ok_or(EscrowError::TokenAccountMissing)?
+        }
+        None => maker_token_a_account
+            .as_mut()
+            .ok_or(EscrowError::TokenAccountMissing)?,
+    };
+
+    let recipient_amount_a_before_transfer = token_a_recipient_account.amount;
+
+    transfer_token(
+        token_a_program.to_account_info().clone(),
+        auction_token_a_vault_account.to_account_info().clone(),
+        token_a_mint_account,
+        token_a_recipient_account.to_account_info().clone(),
+        auction.to_account_info().clone(),
+        token_a_amount,
+        Some(auction_signer_seeds),
+    )?;
+
+    token_a_recipient_account.reload()?;
+
+    assert_received_net(
+        "recipient token A",
+        token_a_mint_account,
+        token_a_recipient_account.amount,
+        recipient_amount_a_before_transfer,
+        token_a_amount,
+    )?;
+
+    harvest_withheld_fees(
+        token_a_program.to_account_info().clone(),
+        token_a_mint_account,
+        auction_token_a_vault_account.to_account_info().clone(),
+    )?;
+
+    close_token_account(
+        token_a_program.to_account_info().clone(),
+        auction_token_a_vault_account.to_account_info().clone(),
+        auction.to_account_info().clone(),
+        funder.to_account_info().clone(),
+        Some(auction_signer_seeds),
+    )?;
+
+    msg!("Auction settled : {}", auction.key());
+
+    emit_cpi!(AuctionSettled {
+        auction: auction.key(),
+        maker: maker.key(),
+        winner: auction.highest_bidder,
+        token_a_amount,
+        token_b_amount: auction.highest_bid_amount,
+        token_b_fee_amount,
+    });
+
+    if auction.pending_refund_amount == 0 {
+        auction.close(funder.to_account_info())?;
+    } else {
+        auction.settled = true;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,331 @@
+use crate::errors::EscrowError;
+use crate::events::BasketOfferTaken;
+use crate::state::{BasketOffer, EscrowState};
+use crate::utils::{
+    assert_associated_token_account, assert_received_net, close_token_account,
+    create_associated_token_account, get_token_account_amount, harvest_withheld_fees, load_mint,
+    load_mint_config, transfer_token,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token_interface::TokenInterface};
+
+#[derive(AnchorDeserialize, AnchorSerialize, Debug)]
+pub struct TakeBasketOfferArgs {
+    pub max_taker_fee_bps: u16,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct TakeBasketOffer<'info> {
+    #[account(seeds = [EscrowState::SEED], bump = escrow_state.bump)]
+    pub escrow_state: Account<'info, EscrowState>,
+    #[account(mut, seeds = [BasketOffer::SEED_PREFIX, maker.key().as_ref(), basket_offer.id.to_le_bytes().as_ref()], bump = basket_offer.bump, close = funder)]
+    pub basket_offer: Account<'info, BasketOffer>,
+    /// CHECK : address of maker wallet
+    #[account(address = basket_offer.maker)]
+    pub maker: UncheckedAccount<'info>,
+    /// CHECK : address of the account which funded the basket offer, receives the rent back
+    #[account(mut, address = basket_offer.funder)]
+    pub funder: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub taker: Signer<'info>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Take a whole basket offer in one transaction.
+///
+/// The remaining accounts are, for each offered leg in order, its mint, its token program,
+/// mint config, the basket offer's vault, the taker's token account, the maker's ATA and the
+/// escrow fee ATA, followed by, for each wanted leg in order, its mint, its token program,
+/// mint config, the taker's token account, the maker's ATA and the escrow fee ATA. Legs may
+/// mix SPL Token and Token-2022 mints, and missing maker and fee ATAs are created with the
+/// taker as payer.
+///
+/// Anything sent to an offered leg's vault on top of the leg amount is returned to the
+/// maker, so the vault can always be closed.
+///
+/// Offered legs pay the taker fee and wanted legs the maker fee. Baskets have no trading
+/// pair, so pair overrides and fee tiers do not apply.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, TakeBasketOffer<'info>>,
+    args: TakeBasketOfferArgs,
+) -> Result<()> {
+    let TakeBasketOffer {
+        escrow_state,
+        basket_offer,
+        maker,
+        funder,
+        taker,
+        associated_token_program,
+        system_program,
+        ..
+    } = ctx.accounts;
+
+    if escrow_state.take_paused {
+        return Err(EscrowError::TakePaused.into());
+    }
+
+    if maker.key == taker.key {
+        return Err(EscrowError::SelfTradeNotAllowed.into());
+    }
+
+    if basket_offer.is_expired(Clock::get()?.unix_timestamp) {
+        return Err(EscrowError::OfferExpired.into());
+    }
+
+    if !basket_offer.is_taker_allowed(taker.key) {
+        return Err(EscrowError::TakerNotAllowed.into());
+    }
+
+    if escrow_state.get_taker_fee_bps(None, None)? > args.max_taker_fee_bps {
+        return Err(EscrowError::SlippageExceeded.into());
+    }
+
+    let offered_leg_count = basket_offer.offered.len();
+
+    if ctx.remaining_accounts.len() != offered_leg_count * 7 + basket_offer.wanted.len() * 6 {
+        return Err(EscrowError::TokenAccountMissing.into());
+    }
+
+    let (offered_leg_accounts, wanted_leg_accounts) =
+        ctx.remaining_accounts.split_at(offered_leg_count * 7);
+
+    let basket_offer_signer_seeds = &[
+        BasketOffer::SEED_PREFIX,
+        maker.key.as_ref(),
+        &basket_offer.id.to_le_bytes(),
+        &[basket_offer.bump],
+    ];
+
+    let mut offered_fee_amounts = Vec::with_capacity(offered_leg_count);
+    let mut wanted_fee_amounts = Vec::with_capacity(basket_offer.wanted.len());
+
+    for (leg, accounts) in basket_offer
+        .offered
+        .iter()
+        .zip(offered_leg_accounts.chunks(7))
+    {
+        let [mint_account, token_program, mint_config, vault_account, taker_token_account, maker_token_account, escrow_fee_account] =
+            accounts
+        else {
+            return Err(EscrowError::TokenAccountMissing.into());
+        };
+
+        let token_program = Interface::<TokenInterface>::try_from(token_program)?;
+        let mint_account = load_mint(mint_account, &leg.mint, &token_program.key())?;
+        let mint_config = load_mint_config(mint_config, &leg.mint)?;
+
+        if !escrow_state.is_mint_permitted(mint_config.as_ref()) {
+            return Err(EscrowError::MintNotPermitted.into());
+        }
+
+        assert_associated_token_account(
+            vault_account,
+            &basket_offer.key(),
+            &leg.mint,
+            &token_program.key(),
+        )?;
+        assert_associated_token_account(
+            maker_token_account,
+            maker.key,
+            &leg.mint,
+            &token_program.key(),
+        )?;
+        assert_associated_token_account(
+            escrow_fee_account,
+            &escrow_state.key(),
+            &leg.mint,
+            &token_program.key(),
+        )?;
+
+        create_associated_token_account(
+            associated_token_program,
+            taker.to_account_info().clone(),
+            maker_token_account.clone(),
+            maker.to_account_info().clone(),
+            mint_account.to_account_info().clone(),
+            token_program.to_account_info().clone(),
+            system_program.to_account_info().clone(),
+        )?;
+        create_associated_token_account(
+            associated_token_program,
+            taker.to_account_info().clone(),
+            escrow_fee_account.clone(),
+            escrow_state.to_account_info().clone(),
+            mint_account.to_account_info().clone(),
+            token_program.to_account_info().clone(),
+            system_program.to_account_info().clone(),
+        )?;
+
+        let fee_amount =
+            escrow_state.get_taker_fee(leg.amount, None, mint_config.as_ref(), None)?;
+        let amount_after_fee = leg
+            .amount
+            .checked_sub(fee_amount)
+            .ok_or(EscrowError::MathError)?;
+
+        let taker_amount_before_transfer = get_token_account_amount(taker_token_account)?;
+
+        transfer_token(
+            token_program.to_account_info().clone(),
+            vault_account.clone(),
+            &mint_account,
+            escrow_fee_account.clone(),
+            basket_offer.to_account_info().clone(),
+            fee_amount,
+            Some(basket_offer_signer_seeds),
+        )?;
+
+        transfer_token(
+            token_program.to_account_info().clone(),
+            vault_account.clone(),
+            &mint_account,
+            taker_token_account.clone(),
+            basket_offer.to_account_info().clone(),
+            amount_after_fee,
+            Some(basket_offer_signer_seeds),
+        )?;
+
+        assert_received_net(
+            "taker basket leg",
+            &mint_account,
+            get_token_account_amount(taker_token_account)?,
+            taker_amount_before_transfer,
+            amount_after_fee,
+        )?;
+
+        let vault_surplus_amount = get_token_account_amount(vault_account)?;
+
+        if vault_surplus_amount != 0 {
+            transfer_token(
+                token_program.to_account_info().clone(),
+                vault_account.clone(),
+                &mint_account,
+                maker_token_account.clone(),
+                basket_offer.to_account_info().clone(),
+                vault_surplus_amount,
+                Some(basket_offer_signer_seeds),
+            )?;
+        }
+
+        harvest_withheld_fees(
+            token_program.to_account_info().clone(),
+            &mint_account,
+            vault_account.clone(),
+        )?;
+
+        close_token_account(
+            token_program.to_account_info().clone(),
+            vault_account.clone(),
+            basket_offer.to_account_info().clone(),
+            funder.to_account_info().clone(),
+            Some(basket_offer_signer_seeds),
+        )?;
+
+        offered_fee_amounts.push(fee_amount);
+    }
+
+    for (leg, accounts) in basket_offer
+        .wanted
+        .iter()
+        .zip(wanted_leg_accounts.chunks(6))
+    {
+        let [mint_account, token_program, mint_config, taker_token_account, maker_token_account, escrow_fee_account] =
+            accounts
+        else {
+            return Err(EscrowError::TokenAccountMissing.into());
+        };
+
+        let token_program = Interface::<TokenInterface>::try_from(token_program)?;
+        let mint_account = load_mint(mint_account, &leg.mint, &token_program.key())?;
+        let mint_config = load_mint_config(mint_config, &leg.mint)?;
+
+        if !escrow_state.is_mint_permitted(mint_config.as_ref()) {
+            return Err(EscrowError::MintNotPermitted.into());
+        }
+
+        assert_associated_token_account(
+            maker_token_account,
+            maker.key,
+            &leg.mint,
+            &token_program.key(),
+        )?;
+        assert_associated_token_account(
+            escrow_fee_account,
+            &escrow_state.key(),
+            &leg.mint,
+            &token_program.key(),
+        )?;
+
+        create_associated_token_account(
+            associated_token_program,
+            taker.to_account_info().clone(),
+            maker_token_account.clone(),
+            maker.to_account_info().clone(),
+            mint_account.to_account_info().clone(),
+            token_program.to_account_info().clone(),
+            system_program.to_account_info().clone(),
+        )?;
+        create_associated_token_account(
+            associated_token_program,
+            taker.to_account_info().clone(),
+            escrow_fee_account.clone(),
+            escrow_state.to_account_info().clone(),
+            mint_account.to_account_info().clone(),
+            token_program.to_account_info().clone(),
+            system_program.to_account_info().clone(),
+        )?;
+
+        let fee_amount =
+            escrow_state.get_maker_fee(leg.amount, None, mint_config.as_ref(), None)?;
+        let amount_after_fee = leg
+            .amount
+            .checked_sub(fee_amount)
+            .ok_or(EscrowError::MathError)?;
+
+        let maker_amount_before_transfer = get_token_account_amount(maker_token_account)?;
+
+        transfer_token(
+            token_program.to_account_info().clone(),
+            taker_token_account.clone(),
+            &mint_account,
+            escrow_fee_account.clone(),
+            taker.to_account_info().clone(),
+            fee_amount,
+            None,
+        )?;
+
+        transfer_token(
+            token_program.to_account_info().clone(),
+            taker_token_account.clone(),
+            &mint_account,
+            maker_token_account.clone(),
+            taker.to_account_info().clone(),
+            amount_after_fee,
+            None,
+        )?;
+
+        assert_received_net(
+            "maker basket leg",
+            &mint_account,
+            get_token_account_amount(maker_token_account)?,
+            maker_amount_before_transfer,
+            amount_after_fee,
+        )?;
+
+        wanted_fee_amounts.push(fee_amount);
+    }
+
+    msg!("Basket offer taken : {}", basket_offer.key());
+
+    emit_cpi!(BasketOfferTaken {
+        basket_offer: basket_offer.key(),
+        maker: maker.key(),
+        taker: taker.key(),
+        offered_fee_amounts,
+        wanted_fee_amounts,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,390 @@
+use crate::{
+    errors::EscrowError,
+    events::OfferTaken,
+    state::{EscrowState, MintConfig, Offer, OfferSide, PairFeeConfig, TraderStats},
+    utils::{
+        assert_balance_invariant, assert_received_net, close_token_account, get_transfer_fee,
+        harvest_withheld_fees, is_native_mint, load_optional_account, store_optional_account,
+        transfer_token, unwrap_sol, verify_merkle_proof, wrap_sol,
+    },
+};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::system_program::{transfer, Transfer};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+#[derive(AnchorDeserialize, AnchorSerialize, Debug)]
+pub struct TakeBidArgs {
+    pub token_a_amount: u64,
+    pub taker_merkle_proof: Vec<[u8; 32]>,
+    /// Minimum token B the taker receives, net of fees
+    pub min_token_b_amount: u64,
+    pub max_taker_fee_bps: u16,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct TakeBid<'info> {
+    #[account(seeds = [EscrowState::SEED], bump = escrow_state.bump)]
+    pub escrow_state: Box<Account<'info, EscrowState>>,
+    #[account(mut, seeds = [Offer::SEED_PREFIX, maker.key().as_ref(), escrow_account.id.to_le_bytes().as_ref()], bump = escrow_account.bump)]
+    pub escrow_account: Box<Account<'info, Offer>>,
+    #[account(address = escrow_account.token_mint_a, mint::token_program = token_a_program)]
+    pub token_a_mint_account: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut, address = escrow_account.token_mint_b, mint::token_program = token_b_program)]
+    pub token_b_mint_account: Box<InterfaceAccount<'info, Mint>>,
+    /// CHECK : mint config PDA of token A, which is empty unless the manager created it
+    #[account(seeds = [MintConfig::SEED_PREFIX, token_a_mint_account.key().as_ref()], bump)]
+    pub token_a_mint_config: UncheckedAccount<'info>,
+    /// CHECK : mint config PDA of token B, which is empty unless the manager created it
+    #[account(seeds = [MintConfig::SEED_PREFIX, token_b_mint_account.key().as_ref()], bump)]
+    pub token_b_mint_config: UncheckedAccount<'info>,
+    /// CHECK : fee override PDA of the trading pair, which is empty unless the manager created it
+    #[account(seeds = [PairFeeConfig::SEED_PREFIX, token_a_mint_account.key().as_ref(), token_b_mint_account.key().as_ref()], bump)]
+    pub pair_fee_config: UncheckedAccount<'info>,
+    /// CHECK : fee tier stats PDA of the maker, which is empty unless the trader created it
+    #[account(mut, seeds = [TraderStats::SEED_PREFIX, maker.key().as_ref()], bump)]
+    pub maker_trader_stats: UncheckedAccount<'info>,
+    /// CHECK : fee tier stats PDA of the taker, which is empty unless the trader created it
+    #[account(mut, seeds = [TraderStats::SEED_PREFIX, taker.key().as_ref()], bump)]
+    pub taker_trader_stats: UncheckedAccount<'info>,
+    #[account(init_if_needed, payer = funding_account, associated_token::mint = token_a_mint_account, associated_token::authority = maker, associated_token::token_program = token_a_program)]
+    pub maker_token_a_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    #[account(mut, associated_token::mint = token_a_mint_account, associated_token::authority = taker, associated_token::token_program = token_a_program)]
+    pub taker_token_a_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    #[account(init_if_needed, payer = funding_account, associated_token::mint = token_b_mint_account, associated_token::authority = taker, associated_token::token_program = token_b_program)]
+    pub taker_token_b_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(init_if_needed, payer = funding_account, associated_token::mint = token_a_mint_account, associated_token::authority = escrow_state, associated_token::token_program = token_a_program)]
+    pub escrow_token_a_fee_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(init_if_needed, payer = funding_account, associated_token::mint = token_b_mint_account, associated_token::authority = escrow_state, associated_token::token_program = token_b_program)]
+    pub escrow_token_b_fee_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, associated_token::mint = token_b_mint_account, associated_token::authority = escrow_account, associated_token::token_program = token_b_program)]
+    pub escrow_token_b_vault_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    /// CHECK : address of maker wallet
+    #[account(mut, address = escrow_account.maker)]
+    pub maker: UncheckedAccount<'info>,
+    /// CHECK : address of the account which funded the bid, receives the rent back once filled
+    #[account(mut, address = escrow_account.funder)]
+    pub funder: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub taker: Signer<'info>,
+    #[account(mut)]
+    pub funding_account: Signer<'info>,
+    pub token_a_program: Interface<'info, TokenInterface>,
+    pub token_b_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Sell `token_a_amount` token A into a bid.
+///
+/// The taker's token A goes to the maker, charged the maker fee, and the vault pays the
+/// pro-rata token B, charged the taker fee. Bids do not pay referral shares.
+///
+/// Native SOL token A is paid straight from the taker's lamports to the maker's system
+/// account, as `take_offer` does for native SOL token B.
+pub fn handler(ctx: Context<TakeBid>, args: TakeBidArgs) -> Result<()> {
+    let TakeBid {
+        escrow_state,
+        escrow_account,
+        token_a_mint_account,
+        token_b_mint_account,
+        token_a_mint_config,
+        token_b_mint_config,
+        pair_fee_config,
+        maker_trader_stats,
+        taker_trader_stats,
+        maker_token_a_account,
+        taker_token_a_account,
+        taker_token_b_account,
+        escrow_token_a_fee_account,
+        escrow_token_b_fee_account,
+        escrow_token_b_vault_account,
+        maker,
+        funder,
+        taker,
+        token_a_program,
+        token_b_program,
+        system_program,
+        ..
+    } = ctx.accounts;
+
+    if escrow_state.take_paused {
+        return Err(EscrowError::TakePaused.into());
+    }
+
+    if escrow_account.side != OfferSide::Bid {
+        return Err(EscrowError::OfferSideMismatch.into());
+    }
+
+    let token_a_mint_config = load_optional_account::<MintConfig>(token_a_mint_config)?;
+    let token_b_mint_config = load_optional_account::<MintConfig>(token_b_mint_config)?;
+
+    if !escrow_state.is_mint_permitted(token_a_mint_config.as_ref())
+        || !escrow_state.is_mint_permitted(token_b_mint_config.as_ref())
+    {
+        return Err(EscrowError::MintNotPermitted.into());
+    }
+
+    // The maker and taker volumes are tracked on separate accounts
+    if maker.key == taker.key {
+        return Err(EscrowError::SelfTradeNotAllowed.into());
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+
+    if escrow_account.is_expired(now) {
+        return Err(EscrowError::OfferExpired.into());
+    }
+
+    if !escrow_account.is_taker_allowed(taker.key) {
+        return Err(EscrowError::TakerNotAllowed.into());
+    }
+
+    // Allowlisted bids require a proof that `hash(taker)` is a leaf of the merkle root
+    if let Some(taker_merkle_root) = escrow_account.taker_merkle_root {
+        let leaf = hashv(&[taker.key.as_ref()]).to_bytes();
+
+        if !verify_merkle_proof(&args.taker_merkle_proof, taker_merkle_root, leaf) {
+            return Err(EscrowError::InvalidMerkleProof.into());
+        }
+    }
+
+    let pair_fee_config = load_optional_account::<PairFeeConfig>(pair_fee_config)?;
+    let mut maker_stats = load_optional_account::<TraderStats>(maker_trader_stats)?;
+    let mut taker_stats = load_optional_account::<TraderStats>(taker_trader_stats)?;
+
+    let escrow_account_signer_seeds = &[
+        Offer::SEED_PREFIX,
+        maker.key.as_ref(),
+        &escrow_account.id.to_le_bytes(),
+        &[escrow_account.bump],
+    ];
+
+    let token_a_amount = args.token_a_amount;
+    let token_b_amount = escrow_account.get_token_b_amount_for_fill(token_a_amount, now)?;
+
+    // The maker receives token A and the taker receives token B, so the fee sides swap
+    let token_a_fee_amount = escrow_state.get_maker_fee(
+        token_a_amount,
+        pair_fee_config.as_ref(),
+        token_a_mint_config.as_ref(),
+        maker_stats.as_ref(),
+    )?;
+    let token_b_fee_amount = escrow_state.get_taker_fee(
+        token_b_amount,
+        pair_fee_config.as_ref(),
+        token_b_mint_config.as_ref(),
+        taker_stats.as_ref(),
+    )?;
+
+    let token_a_to_transfer_after_fee = token_a_amount
+        .checked_sub(token_a_fee_amount)
+        .ok_or(EscrowError::MathError)?;
+    let token_b_to_transfer_after_fee = token_b_amount
+        .checked_sub(token_b_fee_amount)
+        .ok_or(EscrowError::MathError)?;
+
+    let token_b_transfer_fee =
+        get_transfer_fee(token_b_mint_account, token_b_to_transfer_after_fee)?;
+
+    // The taker's limits guard against the terms or fees changing before the fill lands
+    if escrow_state.get_taker_fee_bps(pair_fee_config.as_ref(), taker_stats.as_ref())?
+        > args.max_taker_fee_bps
+        || token_b_to_transfer_after_fee
+            .checked_sub(token_b_transfer_fee)
+            .ok_or(EscrowError::MathError)?
+            < args.min_token_b_amount
+    {
+        return Err(EscrowError::SlippageExceeded.into());
+    }
+
+    let taker_amount_b_before_transfer = taker_token_b_account.amount;
+
+    if is_native_mint(&token_a_mint_account.key()) {
+        // Native SOL is paid straight from the taker's lamports: the fee is wrapped into
+        // the escrow fee account and the rest goes to the maker's system account
+        let maker_lamports_before_transfer = maker.lamports();
+
+        wrap_sol(
+            system_program.to_account_info().clone(),
+            token_a_program.to_account_info().clone(),
+            taker.to_account_info().clone(),
+            escrow_token_a_fee_account.to_account_info().clone(),
+            token_a_fee_amount,
+        )?;
+
+        transfer(
+            CpiContext::new(
+                system_program.to_account_info().clone(),
+                Transfer {
+                    from: taker.to_account_info().clone(),
+                    to: maker.to_account_info().clone(),
+                },
+            ),
+            token_a_to_transfer_after_fee,
+        )?;
+
+        assert_balance_invariant(
+            "maker lamports",
+            maker.lamports(),
+            maker_lamports_before_transfer
+                .checked_add(token_a_to_transfer_after_fee)
+                .ok_or(EscrowError::MathError)?,
+        )?;
+    } else {
+        let taker_token_a_account = taker_token_a_account
+            .as_ref()
+            .ok_or(EscrowError::TokenAccountMissing)?;
+        let maker_token_a_account = maker_token_a_account
+            .as_mut()
+            .ok_or(EscrowError::TokenAccountMissing)?;
+
+        let maker_amount_a_before_transfer = maker_token_a_account.amount;
+
+        transfer_token(
+            token_a_program.to_account_info().clone(),
+            taker_token_a_account.to_account_info().clone(),
+            token_a_mint_account,
+            escrow_token_a_fee_account.to_account_info().clone(),
+            taker.to_account_info().clone(),
+            token_a_fee_amount,
+            None,
+        )?;
+
+        transfer_token(
+            token_a_program.to_account_info().clone(),
+            taker_token_a_account.to_account_info().clone(),
+            token_a_mint_account,
+            maker_token_a_account.to_account_info().clone(),
+            taker.to_account_info().clone(),
+            token_a_to_transfer_after_fee,
+            None,
+        )?;
+
+        maker_token_a_account.reload()?;
+
+        assert_received_net(
+            "maker token A",
+            token_a_mint_account,
+            maker_token_a_account.amount,
+            maker_amount_a_before_transfer,
+            token_a_to_transfer_after_fee,
+        )?;
+    }
+
+    transfer_token(
+        token_b_program.to_account_info().clone(),
+        escrow_token_b_vault_account.to_account_info().clone(),
+        token_b_mint_account,
+        escrow_token_b_fee_account.to_account_info().clone(),
+        escrow_account.to_account_info().clone(),
+        token_b_fee_amount,
+        Some(escrow_account_signer_seeds),
+    )?;
+
+    transfer_token(
+        token_b_program.to_account_info().clone(),
+        escrow_token_b_vault_account.to_account_info().clone(),
+        token_b_mint_account,
+        taker_token_b_account.to_account_info().clone(),
+        escrow_account.to_account_info().clone(),
+        token_b_to_transfer_after_fee,
+        Some(escrow_account_signer_seeds),
+    )?;
+
+    taker_token_b_account.reload()?;
+    escrow_token_b_fee_account.reload()?;
+
+    assert_received_net(
+        "taker token B",
+        token_b_mint_account,
+        taker_token_b_account.amount,
+        taker_amount_b_before_transfer,
+        token_b_to_transfer_after_fee,
+    )?;
+
+    if is_native_mint(&token_b_mint_account.key()) {
+        unwrap_sol(
+            token_b_program.to_account_info().clone(),
+            taker_token_b_account.to_account_info().clone(),
+            taker.to_account_info().clone(),
+            taker_amount_b_before_transfer,
+        )?;
+    }
+
+    escrow_account.record_fill(token_a_amount, token_b_amount)?;
+
+    TraderStats::record_fill(
+        pair_fee_config.as_ref(),
+        token_a_amount,
+        token_a_mint_account.decimals,
+        maker_stats.as_mut(),
+        taker_stats.as_mut(),
+    );
+
+    store_optional_account(maker_trader_stats, maker_stats.as_ref())?;
+    store_optional_account(taker_trader_stats, taker_stats.as_ref())?;
+
+    msg!(
+        "Bid filled : {} token A for {} token B",
+        token_a_amount,
+        token_b_amount
+    );
+
+    emit_cpi!(OfferTaken {
+        offer: escrow_account.key(),
+        maker: maker.key(),
+        taker: taker.key(),
+        referrer: None,
+        side: OfferSide::Bid,
+        token_a_amount,
+        token_b_amount,
+        token_a_fee_amount,
+        token_b_fee_amount,
+        token_a_referral_amount: 0,
+        token_b_referral_amount: 0,
+        token_a_remaining_amount: escrow_account.token_a_remaining_amount()?,
+    });
+
+    // The bid and its vault are only closed once the last token A has been delivered
+    if escrow_account.is_filled()? {
+        escrow_token_b_vault_account.reload()?;
+
+        // Anything sent to the vault outside of the bid goes to the taker, so the vault can
+        // always be closed. Native SOL vaults can be closed with a balance.
+        if escrow_token_b_vault_account.amount != 0 && !is_native_mint(&token_b_mint_account.key())
+        {
+            transfer_token(
+                token_b_program.to_account_info().clone(),
+                escrow_token_b_vault_account.to_account_info().clone(),
+                token_b_mint_account,
+                taker_token_b_account.to_account_info().clone(),
+                escrow_account.to_account_info().clone(),
+                escrow_token_b_vault_account.amount,
+                Some(escrow_account_signer_seeds),
+            )?;
+        }
+
+        harvest_withheld_fees(
+            token_b_program.to_account_info().clone(),
+            token_b_mint_account,
+            escrow_token_b_vault_account.to_account_info().clone(),
+        )?;
+
+        close_token_account(
+            token_b_program.to_account_info().clone(),
+            escrow_token_b_vault_account.to_account_info().clone(),
+            escrow_account.to_account_info().clone(),
+            funder.to_account_info().clone(),
+            Some(escrow_account_signer_seeds),
+        )?;
+
+        escrow_account.close(funder.to_account_info())?;
+    }
+
+    Ok(())
+}
@@ -1,64 +1,181 @@
 use crate::{
     errors::EscrowError,
-    state::{EscrowState, Offer},
-    utils::{close_token_account, transfer_token},
+    events::OfferTaken,
+    state::{EscrowState, MintConfig, Offer, OfferSide, PairFeeConfig, Referrer, TraderStats},
+    utils::{
+        assert_balance_invariant, assert_received_net, close_token_account, get_transfer_fee,
+        harvest_withheld_fees, is_native_mint, load_optional_account, store_optional_account,
+        transfer_token, unwrap_sol, verify_merkle_proof, wrap_sol,
+    },
 };
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::system_program::{transfer, Transfer};
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{Mint, Token, TokenAccount},
+    token_interface::{Mint, TokenAccount, TokenInterface},
 };
 
+#[derive(AnchorDeserialize, AnchorSerialize, Debug)]
+pub struct TakeOfferArgs {
+    pub token_a_amount: u64,
+    pub taker_merkle_proof: Vec<[u8; 32]>,
+    /// Minimum token A the taker receives, net of fees
+    pub expected_token_a_amount: u64,
+    pub max_token_b_amount: u64,
+    pub max_taker_fee_bps: u16,
+}
+
+#[event_cpi]
 #[derive(Accounts)]
 pub struct TakeOffer<'info> {
     #[account(seeds = [EscrowState::SEED], bump = escrow_state.bump)]
     pub escrow_state: Box<Account<'info, EscrowState>>,
-    #[account(mut, seeds = [Offer::SEED_PREFIX, maker.key().as_ref(), escrow_account.id.to_le_bytes().as_ref()], bump = escrow_account.bump, close=funding_account)]
+    #[account(mut, seeds = [Offer::SEED_PREFIX, maker.key().as_ref(), escrow_account.id.to_le_bytes().as_ref()], bump = escrow_account.bump)]
     pub escrow_account: Box<Account<'info, Offer>>,
-    #[account(address = escrow_account.token_mint_a)]
-    pub token_a_mint_account: Account<'info, Mint>,
-    #[account(address = escrow_account.token_mint_b)]
-    pub token_b_mint_account: Account<'info, Mint>,
-    #[account(init_if_needed, payer = funding_account, associated_token::mint = token_b_mint_account, associated_token::authority = maker, associated_token::token_program = token_program)]
-    pub maker_token_b_account: Box<Account<'info, TokenAccount>>,
-    #[account(init_if_needed, payer = funding_account, associated_token::mint = token_a_mint_account, associated_token::authority = taker, associated_token::token_program = token_program)]
-    pub taker_token_a_account: Box<Account<'info, TokenAccount>>,
-    #[account(mut, associated_token::mint = token_b_mint_account, associated_token::authority = taker, associated_token::token_program = token_program)]
-    pub taker_token_b_account: Box<Account<'info, TokenAccount>>,
-    #[account(init_if_needed, payer = funding_account, associated_token::mint = token_a_mint_account, associated_token::authority = escrow_state, associated_token::token_program = token_program)]
-    pub escrow_token_a_fee_account: Box<Account<'info, TokenAccount>>,
-    #[account(init_if_needed, payer = funding_account, associated_token::mint = token_b_mint_account, associated_token::authority = escrow_state, associated_token::token_program = token_program)]
-    pub escrow_token_b_fee_account: Box<Account<'info, TokenAccount>>,
-    #[account(mut, associated_token::mint = token_a_mint_account, associated_token::authority = escrow_account, associated_token::token_program = token_program)]
-    pub escrow_token_a_vault_account: Account<'info, TokenAccount>,
+    #[account(mut, address = escrow_account.token_mint_a, mint::token_program = token_a_program)]
+    pub token_a_mint_account: Box<InterfaceAccount<'info, Mint>>,
+    #[account(address = escrow_account.token_mint_b, mint::token_program = token_b_program)]
+    pub token_b_mint_account: Box<InterfaceAccount<'info, Mint>>,
+    /// CHECK : mint config PDA of token A, which is empty unless the manager created it
+    #[account(seeds = [MintConfig::SEED_PREFIX, token_a_mint_account.key().as_ref()], bump)]
+    pub token_a_mint_config: UncheckedAccount<'info>,
+    /// CHECK : mint config PDA of token B, which is empty unless the manager created it
+    #[account(seeds = [MintConfig::SEED_PREFIX, token_b_mint_account.key().as_ref()], bump)]
+    pub token_b_mint_config: UncheckedAccount<'info>,
+    /// CHECK : fee override PDA of the trading pair, which is empty unless the manager created it
+    #[account(seeds = [PairFeeConfig::SEED_PREFIX, token_a_mint_account.key().as_ref(), token_b_mint_account.key().as_ref()], bump)]
+    pub pair_fee_config: UncheckedAccount<'info>,
+    /// CHECK : fee tier stats PDA of the maker, which is empty unless the trader created it
+    #[account(mut, seeds = [TraderStats::SEED_PREFIX, maker.key().as_ref()], bump)]
+    pub maker_trader_stats: UncheckedAccount<'info>,
+    /// CHECK : fee tier stats PDA of the taker, which is empty unless the trader created it
+    #[account(mut, seeds = [TraderStats::SEED_PREFIX, taker.key().as_ref()], bump)]
+    pub taker_trader_stats: UncheckedAccount<'info>,
+    #[account(init_if_needed, payer = funding_account, associated_token::mint = token_b_mint_account, associated_token::authority = maker, associated_token::token_program = token_b_program)]
+    pub maker_token_b_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    #[account(init_if_needed, payer = funding_account, associated_token::mint = token_a_mint_account, associated_token::authority = taker, associated_token::token_program = token_a_program)]
+    pub taker_token_a_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, associated_token::mint = token_b_mint_account, associated_token::authority = taker, associated_token::token_program = token_b_program)]
+    pub taker_token_b_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    #[account(init_if_needed, payer = funding_account, associated_token::mint = token_a_mint_account, associated_token::authority = escrow_state, associated_token::token_program = token_a_program)]
+    pub escrow_token_a_fee_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(init_if_needed, payer = funding_account, associated_token::mint = token_b_mint_account, associated_token::authority = escrow_state, associated_token::token_program = token_b_program)]
+    pub escrow_token_b_fee_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, associated_token::mint = token_a_mint_account, associated_token::authority = escrow_account, associated_token::token_program = token_a_program)]
+    pub escrow_token_a_vault_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    /// CHECK : wallet of the referrer which routed the trade, receives a share of both fees
+    pub referrer: Option<UncheckedAccount<'info>>,
+    /// Registration of the referrer by the manager, required with a referrer
+    #[account(seeds = [Referrer::SEED_PREFIX, referrer_registration.wallet.as_ref()], bump = referrer_registration.bump)]
+    pub referrer_registration: Option<Box<Account<'info, Referrer>>>,
+    #[account(init_if_needed, payer = funding_account, associated_token::mint = token_a_mint_account, associated_token::authority = referrer, associated_token::token_program = token_a_program)]
+    pub referrer_token_a_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    #[account(init_if_needed, payer = funding_account, associated_token::mint = token_b_mint_account, associated_token::authority = referrer, associated_token::token_program = token_b_program)]
+    pub referrer_token_b_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
     /// CHECK : address of maker wallet
-    #[account(address = escrow_account.maker)]
+    #[account(mut, address = escrow_account.maker)]
     pub maker: UncheckedAccount<'info>,
+    /// CHECK : address of the account which funded the offer, receives the rent back once filled
+    #[account(mut, address = escrow_account.funder)]
+    pub funder: UncheckedAccount<'info>,
+    #[account(mut)]
     pub taker: Signer<'info>,
     #[account(mut)]
     pub funding_account: Signer<'info>,
-    pub token_program: Program<'info, Token>,
+    pub token_a_program: Interface<'info, TokenInterface>,
+    pub token_b_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
-pub fn handler(ctx: Context<TakeOffer>) -> Result<()> {
+pub fn handler(ctx: Context<TakeOffer>, args: TakeOfferArgs) -> Result<()> {
     let TakeOffer {
         escrow_state,
         escrow_account,
+        token_a_mint_account,
+        token_b_mint_account,
+        token_a_mint_config,
+        token_b_mint_config,
+        pair_fee_config,
+        maker_trader_stats,
+        taker_trader_stats,
         maker_token_b_account,
         taker_token_a_account,
         taker_token_b_account,
         escrow_token_a_fee_account,
         escrow_token_b_fee_account,
         escrow_token_a_vault_account,
+        referrer,
+        referrer_registration,
+        referrer_token_a_account,
+        referrer_token_b_account,
         maker,
+        funder,
         taker,
-        token_program,
-        funding_account,
+        token_a_program,
+        token_b_program,
+        system_program,
         ..
     } = ctx.accounts;
 
+    if escrow_state.take_paused {
+        return Err(EscrowError::TakePaused.into());
+    }
+
+    if escrow_account.side != OfferSide::Ask {
+        return Err(EscrowError::OfferSideMismatch.into());
+    }
+
+    let token_a_mint_config = load_optional_account::<MintConfig>(token_a_mint_config)?;
+    let token_b_mint_config = load_optional_account::<MintConfig>(token_b_mint_config)?;
+
+    if !escrow_state.is_mint_permitted(token_a_mint_config.as_ref())
+        || !escrow_state.is_mint_permitted(token_b_mint_config.as_ref())
+    {
+        return Err(EscrowError::MintNotPermitted.into());
+    }
+
+    // The maker and taker volumes are tracked on separate accounts
+    if maker.key == taker.key {
+        return Err(EscrowError::SelfTradeNotAllowed.into());
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+
+    if escrow_account.is_expired(now) {
+        return Err(EscrowError::OfferExpired.into());
+    }
+
+    if !escrow_account.is_taker_allowed(taker.key) {
+        return Err(EscrowError::TakerNotAllowed.into());
+    }
+
+    // Allowlisted offers require a proof that `hash(taker)` is a leaf of the merkle root
+    if let Some(taker_merkle_root) = escrow_account.taker_merkle_root {
+        let leaf = hashv(&[taker.key.as_ref()]).to_bytes();
+
+        if !verify_merkle_proof(&args.taker_merkle_proof, taker_merkle_root, leaf) {
+            return Err(EscrowError::InvalidMerkleProof.into());
+        }
+    }
+
+    // Only wallets registered by the manager can be paid a referral share
+    if let Some(referrer) = referrer {
+        if referrer.key == taker.key
+            || referrer_registration
+                .as_ref()
+                .map(|registration| registration.wallet)
+                != Some(referrer.key())
+        {
+            return Err(EscrowError::InvalidReferrer.into());
+        }
+    }
+
+    let pair_fee_config = load_optional_account::<PairFeeConfig>(pair_fee_config)?;
+    let mut maker_stats = load_optional_account::<TraderStats>(maker_trader_stats)?;
+    let mut taker_stats = load_optional_account::<TraderStats>(taker_trader_stats)?;
+
     let escrow_account_signer_seeds = &[
         Offer::SEED_PREFIX,
         maker.key.as_ref(),
@@ -66,53 +183,197 @@ pub fn handler(ctx: Context<TakeOffer>) -> Result<()> {
         &[escrow_account.bump],
     ];
 
-    let vault_amount_a = escrow_token_a_vault_account.amount;
-    let taker_amount_a_before_transfer = taker_token_a_account.amount;
-    let maker_amount_b_before_transfer = maker_token_b_account.amount;
+    let token_a_amount = args.token_a_amount;
+    let token_b_amount = escrow_account.get_token_b_amount_for_fill(token_a_amount, now)?;
 
-    let token_b_fee_amount = escrow_state.get_token_b_fee(vault_amount_a)?;
+    let token_a_fee_amount = escrow_state.get_token_a_fee(
+        token_a_amount,
+        pair_fee_config.as_ref(),
+        token_a_mint_config.as_ref(),
+        taker_stats.as_ref(),
+    )?;
 
-    transfer_token(
-        token_program.to_account_info().clone(),
-        taker_token_b_account.to_account_info().clone(),
-        escrow_token_b_fee_account.to_account_info().clone(),
-        taker.to_account_info().clone(),
-        token_b_fee_amount,
-        None,
+    let token_a_referral_amount = match referrer {
+        Some(_) => escrow_state.get_referral_amount(token_a_fee_amount)?,
+        None => 0,
+    };
+    let token_a_protocol_fee_amount = token_a_fee_amount
+        .checked_sub(token_a_referral_amount)
+        .ok_or(EscrowError::MathError)?;
+
+    let token_a_to_transfer_after_fee = token_a_amount
+        .checked_sub(token_a_fee_amount)
+        .ok_or(EscrowError::MathError)?;
+
+    let token_a_transfer_fee =
+        get_transfer_fee(token_a_mint_account, token_a_to_transfer_after_fee)?;
+
+    // The taker's limits guard against the terms or fees changing before the fill lands
+    if token_b_amount > args.max_token_b_amount
+        || escrow_state.get_taker_fee_bps(pair_fee_config.as_ref(), taker_stats.as_ref())?
+            > args.max_taker_fee_bps
+        || token_a_to_transfer_after_fee
+            .checked_sub(token_a_transfer_fee)
+            .ok_or(EscrowError::MathError)?
+            < args.expected_token_a_amount
+    {
+        return Err(EscrowError::SlippageExceeded.into());
+    }
+
+    let taker_amount_a_before_transfer = taker_token_a_account.amount;
+
+    let token_b_fee_amount = escrow_state.get_token_b_fee(
+        token_b_amount,
+        pair_fee_config.as_ref(),
+        token_b_mint_config.as_ref(),
+        maker_stats.as_ref(),
     )?;
 
-    let token_b_to_transfer_after_fee = escrow_account
-        .token_b_wanted_amount
+    let token_b_referral_amount = match referrer {
+        Some(_) => escrow_state.get_referral_amount(token_b_fee_amount)?,
+        None => 0,
+    };
+    let token_b_protocol_fee_amount = token_b_fee_amount
+        .checked_sub(token_b_referral_amount)
+        .ok_or(EscrowError::MathError)?;
+
+    let token_b_to_transfer_after_fee = token_b_amount
         .checked_sub(token_b_fee_amount)
         .ok_or(EscrowError::MathError)?;
 
-    transfer_token(
-        token_program.to_account_info().clone(),
-        taker_token_b_account.to_account_info().clone(),
-        maker_token_b_account.to_account_info().clone(),
-        taker.to_account_info().clone(),
-        token_b_to_transfer_after_fee,
-        None,
-    )?;
+    if is_native_mint(&token_b_mint_account.key()) {
+        // Native SOL is paid straight from the taker's lamports: the fee is wrapped into
+        // the escrow fee account and the referrer's wSOL account, and the rest goes to the
+        // maker's system account
+        let maker_lamports_before_transfer = maker.lamports();
+
+        wrap_sol(
+            system_program.to_account_info().clone(),
+            token_b_program.to_account_info().clone(),
+            taker.to_account_info().clone(),
+            escrow_token_b_fee_account.to_account_info().clone(),
+            token_b_protocol_fee_amount,
+        )?;
 
-    let token_a_fee_amount = escrow_state.get_token_a_fee(vault_amount_a)?;
+        if token_b_referral_amount != 0 {
+            let referrer_token_b_account = referrer_token_b_account
+                .as_ref()
+                .ok_or(EscrowError::TokenAccountMissing)?;
+
+            wrap_sol(
+                system_program.to_account_info().clone(),
+                token_b_program.to_account_info().clone(),
+                taker.to_account_info().clone(),
+                referrer_token_b_account.to_account_info().clone(),
+                token_b_referral_amount,
+            )?;
+        }
+
+        transfer(
+            CpiContext::new(
+                system_program.to_account_info().clone(),
+                Transfer {
+                    from: taker.to_account_info().clone(),
+                    to: maker.to_account_info().clone(),
+                },
+            ),
+            token_b_to_transfer_after_fee,
+        )?;
+
+        assert_balance_invariant(
+            "maker lamports",
+            maker.lamports(),
+            maker_lamports_before_transfer
+                .checked_add(token_b_to_transfer_after_fee)
+                .ok_or(EscrowError::MathError)?,
+        )?;
+    } else {
+        let taker_token_b_account = taker_token_b_account
+            .as_ref()
+            .ok_or(EscrowError::TokenAccountMissing)?;
+        let maker_token_b_account = maker_token_b_account
+            .as_mut()
+            .ok_or(EscrowError::TokenAccountMissing)?;
+
+        let maker_amount_b_before_transfer = maker_token_b_account.amount;
+
+        transfer_token(
+            token_b_program.to_account_info().clone(),
+            taker_token_b_account.to_account_info().clone(),
+            token_b_mint_account,
+            escrow_token_b_fee_account.to_account_info().clone(),
+            taker.to_account_info().clone(),
+            token_b_protocol_fee_amount,
+            None,
+        )?;
+
+        if token_b_referral_amount != 0 {
+            let referrer_token_b_account = referrer_token_b_account
+                .as_ref()
+                .ok_or(EscrowError::TokenAccountMissing)?;
+
+            transfer_token(
+                token_b_program.to_account_info().clone(),
+                taker_token_b_account.to_account_info().clone(),
+                token_b_mint_account,
+                referrer_token_b_account.to_account_info().clone(),
+                taker.to_account_info().clone(),
+                token_b_referral_amount,
+                None,
+            )?;
+        }
+
+        transfer_token(
+            token_b_program.to_account_info().clone(),
+            taker_token_b_account.to_account_info().clone(),
+            token_b_mint_account,
+            maker_token_b_account.to_account_info().clone(),
+            taker.to_account_info().clone(),
+            token_b_to_transfer_after_fee,
+            None,
+        )?;
+
+        maker_token_b_account.reload()?;
+
+        assert_received_net(
+            "maker token B",
+            token_b_mint_account,
+            maker_token_b_account.amount,
+            maker_amount_b_before_transfer,
+            token_b_to_transfer_after_fee,
+        )?;
+    }
 
     transfer_token(
-        token_program.to_account_info().clone(),
+        token_a_program.to_account_info().clone(),
         escrow_token_a_vault_account.to_account_info().clone(),
+        token_a_mint_account,
         escrow_token_a_fee_account.to_account_info().clone(),
         escrow_account.to_account_info().clone(),
-        token_a_fee_amount,
+        token_a_protocol_fee_amount,
         Some(escrow_account_signer_seeds),
     )?;
 
-    let token_a_to_transfer_after_fee = vault_amount_a
-        .checked_sub(token_a_fee_amount)
-        .ok_or(EscrowError::MathError)?;
+    if token_a_referral_amount != 0 {
+        let referrer_token_a_account = referrer_token_a_account
+            .as_ref()
+            .ok_or(EscrowError::TokenAccountMissing)?;
+
+        transfer_token(
+            token_a_program.to_account_info().clone(),
+            escrow_token_a_vault_account.to_account_info().clone(),
+            token_a_mint_account,
+            referrer_token_a_account.to_account_info().clone(),
+            escrow_account.to_account_info().clone(),
+            token_a_referral_amount,
+            Some(escrow_account_signer_seeds),
+        )?;
+    }
 
     transfer_token(
-        token_program.to_account_info().clone(),
+        token_a_program.to_account_info().clone(),
         escrow_token_a_vault_account.to_account_info().clone(),
+        token_a_mint_account,
         taker_token_a_account.to_account_info().clone(),
         escrow_account.to_account_info().clone(),
         token_a_to_transfer_after_fee,
@@ -120,33 +381,94 @@ pub fn handler(ctx: Context<TakeOffer>) -> Result<()> {
     )?;
 
     taker_token_a_account.reload()?;
-    maker_token_b_account.reload()?;
     escrow_token_a_fee_account.reload()?;
 
-    assert_eq!(
+    assert_received_net(
+        "taker token A",
+        token_a_mint_account,
         taker_token_a_account.amount,
-        taker_amount_a_before_transfer
-            .checked_add(vault_amount_a)
-            .ok_or(EscrowError::MathError)?
-            .checked_sub(token_a_fee_amount)
-            .ok_or(EscrowError::MathError)?
+        taker_amount_a_before_transfer,
+        token_a_to_transfer_after_fee,
+    )?;
+
+    if is_native_mint(&token_a_mint_account.key()) {
+        unwrap_sol(
+            token_a_program.to_account_info().clone(),
+            taker_token_a_account.to_account_info().clone(),
+            taker.to_account_info().clone(),
+            taker_amount_a_before_transfer,
+        )?;
+    }
+
+    escrow_account.record_fill(token_a_amount, token_b_amount)?;
+
+    TraderStats::record_fill(
+        pair_fee_config.as_ref(),
+        token_a_amount,
+        token_a_mint_account.decimals,
+        maker_stats.as_mut(),
+        taker_stats.as_mut(),
     );
-    assert_eq!(
-        maker_token_b_account.amount,
-        maker_amount_b_before_transfer
-            .checked_add(escrow_account.token_b_wanted_amount)
-            .ok_or(EscrowError::MathError)?
-            .checked_sub(token_b_fee_amount)
-            .ok_or(EscrowError::MathError)?
+
+    store_optional_account(maker_trader_stats, maker_stats.as_ref())?;
+    store_optional_account(taker_trader_stats, taker_stats.as_ref())?;
+
+    msg!(
+        "Offer filled : {} token A for {} token B",
+        token_a_amount,
+        token_b_amount
     );
 
-    close_token_account(
-        token_program.to_account_info().clone(),
-        escrow_token_a_vault_account.to_account_info().clone(),
-        escrow_account.to_account_info().clone(),
-        funding_account.to_account_info().clone(),
-        Some(escrow_account_signer_seeds),
-    )?;
+    emit_cpi!(OfferTaken {
+        offer: escrow_account.key(),
+        maker: maker.key(),
+        taker: taker.key(),
+        referrer: referrer.as_ref().map(|referrer| referrer.key()),
+        side: OfferSide::Ask,
+        token_a_amount,
+        token_b_amount,
+        token_a_fee_amount: token_a_protocol_fee_amount,
+        token_b_fee_amount: token_b_protocol_fee_amount,
+        token_a_referral_amount,
+        token_b_referral_amount,
+        token_a_remaining_amount: escrow_account.token_a_remaining_amount()?,
+    });
+
+    // The offer and its vault are only closed once the last token A has been taken
+    if escrow_account.is_filled()? {
+        escrow_token_a_vault_account.reload()?;
+
+        // Anything sent to the vault outside of the offer goes to the taker, so the vault
+        // can always be closed. Native SOL vaults can be closed with a balance.
+        if escrow_token_a_vault_account.amount != 0 && !is_native_mint(&token_a_mint_account.key())
+        {
+            transfer_token(
+                token_a_program.to_account_info().clone(),
+                escrow_token_a_vault_account.to_account_info().clone(),
+                token_a_mint_account,
+                taker_token_a_account.to_account_info().clone(),
+                escrow_account.to_account_info().clone(),
+                escrow_token_a_vault_account.amount,
+                Some(escrow_account_signer_seeds),
+            )?;
+        }
+
+        harvest_withheld_fees(
+            token_a_program.to_account_info().clone(),
+            token_a_mint_account,
+            escrow_token_a_vault_account.to_account_info().clone(),
+        )?;
+
+        close_token_account(
+            token_a_program.to_account_info().clone(),
+            escrow_token_a_vault_account.to_account_info().clone(),
+            escrow_account.to_account_info().clone(),
+            funder.to_account_info().clone(),
+            Some(escrow_account_signer_seeds),
+        )?;
+
+        escrow_account.close(funder.to_account_info())?;
+    }
 
     Ok(())
 }
@@ -0,0 +1,191 @@
+use crate::errors::EscrowError;
+use crate::events::OfferUpdated;
+use crate::state::{EscrowState, Offer, OfferSide};
+use crate::utils::{
+    assert_balance_invariant, get_transfer_fee, is_native_mint, transfer_token, unwrap_sol,
+    wrap_sol,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug)]
+pub struct UpdateOfferArgs {
+    pub token_b_wanted_amount: u64,
+    pub token_a_top_up_amount: u64,
+    pub token_a_withdraw_amount: u64,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpdateOffer<'info> {
+    #[account(seeds = [EscrowState::SEED], bump = escrow_state.bump)]
+    pub escrow_state: Account<'info, EscrowState>,
+    #[account(mut, seeds = [Offer::SEED_PREFIX, maker.key().as_ref(), escrow_account.id.to_le_bytes().as_ref()], bump = escrow_account.bump)]
+    pub escrow_account: Account<'info, Offer>,
+    #[account(address = escrow_account.token_mint_a, mint::token_program = token_program)]
+    pub token_a_mint_account: InterfaceAccount<'info, Mint>,
+    #[account(init_if_needed, payer = funding_account, associated_token::mint = token_a_mint_account, associated_token::authority = maker, associated_token::token_program = token_program)]
+    pub maker_token_a_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, associated_token::mint = token_a_mint_account, associated_token::authority = escrow_account, associated_token::token_program = token_program)]
+    pub escrow_token_a_vault_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, address = escrow_account.maker)]
+    pub maker: Signer<'info>,
+    #[account(mut)]
+    pub funding_account: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Reprice an open offer and optionally resize it.
+///
+/// The amended size and price replace the offer's terms, so any earlier partial fills
+/// are no longer part of the pro-rata calculation. The offer also loses its place in time
+/// and no longer rests ahead of offers made before the update when matched.
+pub fn handler(ctx: Context<UpdateOffer>, args: UpdateOfferArgs) -> Result<()> {
+    let UpdateOffer {
+        escrow_state,
+        escrow_account,
+        token_a_mint_account,
+        maker_token_a_account,
+        escrow_token_a_vault_account,
+        maker,
+        token_program,
+        system_program,
+        ..
+    } = ctx.accounts;
+
+    if escrow_state.make_paused {
+        return Err(EscrowError::MakePaused.into());
+    }
+
+    if escrow_account.side != OfferSide::Ask {
+        return Err(EscrowError::OfferSideMismatch.into());
+    }
+
+    let escrow_account_signer_seeds = &[
+        Offer::SEED_PREFIX,
+        maker.key.as_ref(),
+        &escrow_account.id.to_le_bytes(),
+        &[escrow_account.bump],
+    ];
+
+    let is_native = is_native_mint(&token_a_mint_account.key());
+    let vault_amount_before_update = escrow_token_a_vault_account.amount;
+
+    if args.token_a_top_up_amount != 0 {
+        if is_native {
+            wrap_sol(
+                system_program.to_account_info().clone(),
+                token_program.to_account_info().clone(),
+                maker.to_account_info().clone(),
+                escrow_token_a_vault_account.to_account_info().clone(),
+                args.token_a_top_up_amount,
+            )?;
+        } else {
+            let maker_token_a_account = maker_token_a_account
+                .as_ref()
+                .ok_or(EscrowError::TokenAccountMissing)?;
+
+            transfer_token(
+                token_program.to_account_info().clone(),
+                maker_token_a_account.to_account_info().clone(),
+                token_a_mint_account,
+                escrow_token_a_vault_account.to_account_info().clone(),
+                maker.to_account_info().clone(),
+                args.token_a_top_up_amount,
+                None,
+            )?;
+        }
+    }
+
+    let token_a_top_up_received_amount = args
+        .token_a_top_up_amount
+        .checked_sub(get_transfer_fee(
+            token_a_mint_account,
+            args.token_a_top_up_amount,
+        )?)
+        .ok_or(EscrowError::MathError)?;
+
+    let token_a_remaining_amount = escrow_account
+        .token_a_remaining_amount()?
+        .checked_add(token_a_top_up_received_amount)
+        .ok_or(EscrowError::MathError)?
+        .checked_sub(args.token_a_withdraw_amount)
+        .ok_or(EscrowError::InvalidUpdateAmount)?;
+
+    if token_a_remaining_amount == 0 {
+        return Err(EscrowError::InvalidUpdateAmount.into());
+    }
+
+    if args.token_a_withdraw_amount != 0 {
+        let maker_token_a_account = maker_token_a_account
+            .as_ref()
+            .ok_or(EscrowError::TokenAccountMissing)?;
+        let maker_token_a_amount_before_transfer = maker_token_a_account.amount;
+
+        transfer_token(
+            token_program.to_account_info().clone(),
+            escrow_token_a_vault_account.to_account_info().clone(),
+            token_a_mint_account,
+            maker_token_a_account.to_account_info().clone(),
+            escrow_account.to_account_info().clone(),
+            args.token_a_withdraw_amount,
+            Some(escrow_account_signer_seeds),
+        )?;
+
+        if is_native {
+            unwrap_sol(
+                token_program.to_account_info().clone(),
+                maker_token_a_account.to_account_info().clone(),
+                maker.to_account_info().clone(),
+                maker_token_a_amount_before_transfer,
+            )?;
+        }
+    }
+
+    escrow_token_a_vault_account.reload()?;
+
+    assert_balance_invariant(
+        "vault token A",
+        escrow_token_a_vault_account.amount,
+        vault_amount_before_update
+            .checked_add(token_a_top_up_received_amount)
+            .ok_or(EscrowError::MathError)?
+            .checked_sub(args.token_a_withdraw_amount)
+            .ok_or(EscrowError::MathError)?,
+    )?;
+
+    escrow_account.token_a_filled_amount = 0;
+    escrow_account.token_b_filled_amount = 0;
+    escrow_account.created_at = Clock::get()?.unix_timestamp;
+
+    let offer = Offer::write(
+        escrow_account,
+        None,
+        None,
+        None,
+        None,
+        Some(token_a_remaining_amount),
+        Some(args.token_b_wanted_amount),
+        None,
+    )?;
+
+    offer.assert_is_valid_amounts()?;
+    // A repriced Dutch auction must still start above its end amount
+    offer.assert_is_valid_kind()?;
+
+    msg!("Offer updated successfully : {:?}", offer);
+
+    emit_cpi!(OfferUpdated {
+        offer: escrow_account.key(),
+        maker: offer.maker,
+        token_a_offered_amount: offer.token_a_offered_amount,
+        token_b_wanted_amount: offer.token_b_wanted_amount,
+    });
+
+    Ok(())
+}
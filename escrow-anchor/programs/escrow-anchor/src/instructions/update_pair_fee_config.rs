@@ -0,0 +1,52 @@
+use crate::events::FeesUpdated;
+use crate::state::{EscrowState, PairFeeConfig};
+use crate::utils::assert_is_bps_in_range;
+use anchor_lang::prelude::*;
+
+#[derive(AnchorDeserialize, AnchorSerialize, Debug, Default)]
+pub struct UpdatePairFeeConfigArgs {
+    pub maker_fee_bps: u16,
+    pub taker_fee_bps: u16,
+    pub volume_per_token_a: u64,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpdatePairFeeConfig<'info> {
+    #[account(seeds = [EscrowState::SEED], bump = escrow_state.bump)]
+    pub escrow_state: Account<'info, EscrowState>,
+    #[account(address = escrow_state.manager)]
+    pub escrow_manager: Signer<'info>,
+    #[account(mut, seeds = [PairFeeConfig::SEED_PREFIX, pair_fee_config.token_mint_a.as_ref(), pair_fee_config.token_mint_b.as_ref()], bump = pair_fee_config.bump)]
+    pub pair_fee_config: Account<'info, PairFeeConfig>,
+}
+
+pub fn handler(ctx: Context<UpdatePairFeeConfig>, args: UpdatePairFeeConfigArgs) -> Result<()> {
+    assert_is_bps_in_range(args.maker_fee_bps)?;
+    assert_is_bps_in_range(args.taker_fee_bps)?;
+
+    let UpdatePairFeeConfig {
+        pair_fee_config, ..
+    } = ctx.accounts;
+
+    let pair_fee_config_info = PairFeeConfig::write(
+        pair_fee_config,
+        None,
+        None,
+        Some(args.maker_fee_bps),
+        Some(args.taker_fee_bps),
+        Some(args.volume_per_token_a),
+        None,
+    )?;
+
+    msg!("Updated pair fee config : {:?}", pair_fee_config_info);
+
+    emit_cpi!(FeesUpdated {
+        token_mint_a: Some(pair_fee_config_info.token_mint_a),
+        token_mint_b: Some(pair_fee_config_info.token_mint_b),
+        maker_fee_bps: pair_fee_config_info.maker_fee_bps,
+        taker_fee_bps: pair_fee_config_info.taker_fee_bps,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,136 @@
+use crate::events::BidRefundWithdrawn;
+use crate::state::{Auction, BidRefund};
+use crate::utils::{
+    assert_received_net, close_token_account, harvest_withheld_fees, transfer_token,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct WithdrawBidRefund<'info> {
+    #[account(mut, seeds = [Auction::SEED_PREFIX, auction.maker.as_ref(), auction.id.to_le_bytes().as_ref()], bump = auction.bump)]
+    pub auction: Box<Account<'info, Auction>>,
+    #[account(mut, seeds = [BidRefund::SEED_PREFIX, auction.key().as_ref(), bidder.key().as_ref()], bump = bid_refund.bump, close = refund_funder)]
+    pub bid_refund: Box<Account<'info, BidRefund>>,
+    #[account(mut, address = auction.token_mint_b, mint::token_program = token_program)]
+    pub token_b_mint_account: Box<InterfaceAccount<'info, Mint>>,
+    #[account(init_if_needed, payer = bidder, associated_token::mint = token_b_mint_account, associated_token::authority = bidder, associated_token::token_program = token_program)]
+    pub bidder_token_b_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, associated_token::mint = token_b_mint_account, associated_token::authority = auction, associated_token::token_program = token_program)]
+    pub auction_token_b_bid_vault_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    /// CHECK : address of the account which funded the refund, receives its rent back
+    #[account(mut, address = bid_refund.funder)]
+    pub refund_funder: UncheckedAccount<'info>,
+    /// CHECK : address of the account which funded the auction, receives the rent back once
+    /// the auction is settled and the last refund is withdrawn
+    #[account(mut, address = auction.funder)]
+    pub funder: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Withdraw the token B of outbid bids from an auction's bid vault.
+///
+/// Refunds can be withdrawn at any time, including while the escrow is paused. The last
+/// withdrawal after settlement closes the bid vault and the auction.
+pub fn handler(ctx: Context<WithdrawBidRefund>) -> Result<()> {
+    let WithdrawBidRefund {
+        auction,
+        bid_refund,
+        token_b_mint_account,
+        bidder_token_b_account,
+        auction_token_b_bid_vault_account,
+        funder,
+        bidder,
+        token_program,
+        ..
+    } = ctx.accounts;
+
+    let auction_maker = auction.maker;
+    let auction_signer_seeds = &[
+        Auction::SEED_PREFIX,
+        auction_maker.as_ref(),
+        &auction.id.to_le_bytes(),
+        &[auction.bump],
+    ];
+
+    let refund_amount = bid_refund.amount;
+    let bidder_amount_before_transfer = bidder_token_b_account.amount;
+
+    transfer_token(
+        token_program.to_account_info().clone(),
+        auction_token_b_bid_vault_account.to_account_info().clone(),
+        token_b_mint_account,
+        bidder_token_b_account.to_account_info().clone(),
+        auction.to_account_info().clone(),
+        refund_amount,
+        Some(auction_signer_seeds),
+    )?;
+
+    bidder_token_b_account.reload()?;
+
+    assert_received_net(
+        "bidder token B",
+        token_b_mint_account,
+        bidder_token_b_account.amount,
+        bidder_amount_before_transfer,
+        refund_amount,
+    )?;
+
+    auction.record_refund_withdrawal(refund_amount)?;
+
+    msg!(
+        "Bid refund withdrawn : {} token B from {}",
+        refund_amount,
+        auction.key()
+    );
+
+    emit_cpi!(BidRefundWithdrawn {
+        auction: auction.key(),
+        bidder: bidder.key(),
+        amount: refund_amount,
+    });
+
+    if auction.settled && auction.pending_refund_amount == 0 {
+        auction_token_b_bid_vault_account.reload()?;
+
+        // Anything sent to the bid vault after settlement goes to the last bidder, so the
+        // vault can always be closed
+        if auction_token_b_bid_vault_account.amount != 0 {
+            transfer_token(
+                token_program.to_account_info().clone(),
+                auction_token_b_bid_vault_account.to_account_info().clone(),
+                token_b_mint_account,
+                bidder_token_b_account.to_account_info().clone(),
+                auction.to_account_info().clone(),
+                auction_token_b_bid_vault_account.amount,
+                Some(auction_signer_seeds),
+            )?;
+        }
+
+        harvest_withheld_fees(
+            token_program.to_account_info().clone(),
+            token_b_mint_account,
+            auction_token_b_bid_vault_account.to_account_info().clone(),
+        )?;
+
+        close_token_account(
+            token_program.to_account_info().clone(),
+            auction_token_b_bid_vault_account.to_account_info().clone(),
+            auction.to_account_info().clone(),
+            funder.to_account_info().clone(),
+            Some(auction_signer_seeds),
+        )?;
+
+        auction.close(funder.to_account_info())?;
+    }
+
+    Ok(())
+}
@@ -2,14 +2,23 @@ use anchor_lang::prelude::*;
 
 pub mod consts;
 pub mod errors;
+pub mod events;
 pub mod instructions;
 pub mod state;
 pub mod utils;
 
 use crate::instructions::*;
 use crate::instructions::{
-    collect_fee::CollectFeeArgs, initialize::InitializeArgs, make_offer::MakeOfferArgs,
-    set_fees::SetFeesArgs,
+    collect_fee::CollectFeeArgs, create_auction::CreateAuctionArgs,
+    create_pair_fee_config::CreatePairFeeConfigArgs, initialize::InitializeArgs,
+    make_basket_offer::MakeBasketOfferArgs, make_bid::MakeBidArgs, make_offer::MakeOfferArgs,
+    place_bid::PlaceBidArgs, set_fee_rounding_mode::SetFeeRoundingModeArgs,
+    set_fee_split_config::SetFeeSplitConfigArgs, set_fee_tiers::SetFeeTiersArgs,
+    set_fees::SetFeesArgs, set_mint_config::SetMintConfigArgs,
+    set_mint_list_mode::SetMintListModeArgs, set_pause::SetPauseArgs,
+    set_referral_share::SetReferralShareArgs, take_basket_offer::TakeBasketOfferArgs,
+    take_bid::TakeBidArgs, take_offer::TakeOfferArgs, update_offer::UpdateOfferArgs,
+    update_pair_fee_config::UpdatePairFeeConfigArgs,
 };
 
 declare_id!("2izpriWVFuFivHicKpjJq3F7K8RKTs8qGcsvZBod7gSQ");
@@ -30,20 +39,182 @@ pub mod escrow_anchor {
         set_manager::handler(ctx)?;
         Ok(())
     }
+    pub fn accept_manager(ctx: Context<AcceptManager>) -> Result<()> {
+        accept_manager::handler(ctx)?;
+        Ok(())
+    }
+    pub fn cancel_manager_transfer(ctx: Context<CancelManagerTransfer>) -> Result<()> {
+        cancel_manager_transfer::handler(ctx)?;
+        Ok(())
+    }
+    pub fn migrate_escrow_state(ctx: Context<MigrateEscrowState>) -> Result<()> {
+        migrate_escrow_state::handler(ctx)?;
+        Ok(())
+    }
+    pub fn set_pause(ctx: Context<SetPause>, args: SetPauseArgs) -> Result<()> {
+        set_pause::handler(ctx, args)?;
+        Ok(())
+    }
+    pub fn set_mint_config(ctx: Context<SetMintConfig>, args: SetMintConfigArgs) -> Result<()> {
+        set_mint_config::handler(ctx, args)?;
+        Ok(())
+    }
+    pub fn set_mint_list_mode(
+        ctx: Context<SetMintListMode>,
+        args: SetMintListModeArgs,
+    ) -> Result<()> {
+        set_mint_list_mode::handler(ctx, args)?;
+        Ok(())
+    }
+    pub fn set_fee_rounding_mode(
+        ctx: Context<SetFeeRoundingMode>,
+        args: SetFeeRoundingModeArgs,
+    ) -> Result<()> {
+        set_fee_rounding_mode::handler(ctx, args)?;
+        Ok(())
+    }
+    pub fn set_referral_share(
+        ctx: Context<SetReferralShare>,
+        args: SetReferralShareArgs,
+    ) -> Result<()> {
+        set_referral_share::handler(ctx, args)?;
+        Ok(())
+    }
+    pub fn register_referrer(ctx: Context<RegisterReferrer>) -> Result<()> {
+        register_referrer::handler(ctx)?;
+        Ok(())
+    }
+    pub fn remove_referrer(ctx: Context<RemoveReferrer>) -> Result<()> {
+        remove_referrer::handler(ctx)?;
+        Ok(())
+    }
+    pub fn create_pair_fee_config(
+        ctx: Context<CreatePairFeeConfig>,
+        args: CreatePairFeeConfigArgs,
+    ) -> Result<()> {
+        create_pair_fee_config::handler(ctx, args)?;
+        Ok(())
+    }
+    pub fn update_pair_fee_config(
+        ctx: Context<UpdatePairFeeConfig>,
+        args: UpdatePairFeeConfigArgs,
+    ) -> Result<()> {
+        update_pair_fee_config::handler(ctx, args)?;
+        Ok(())
+    }
+    pub fn close_pair_fee_config(ctx: Context<ClosePairFeeConfig>) -> Result<()> {
+        close_pair_fee_config::handler(ctx)?;
+        Ok(())
+    }
     pub fn collect_fee(ctx: Context<CollectFee>, args: CollectFeeArgs) -> Result<()> {
         collect_fee::handler(ctx, args)?;
         Ok(())
     }
+    pub fn set_fee_tiers(ctx: Context<SetFeeTiers>, args: SetFeeTiersArgs) -> Result<()> {
+        set_fee_tiers::handler(ctx, args)?;
+        Ok(())
+    }
+    pub fn set_fee_split_config(
+        ctx: Context<SetFeeSplitConfig>,
+        args: SetFeeSplitConfigArgs,
+    ) -> Result<()> {
+        set_fee_split_config::handler(ctx, args)?;
+        Ok(())
+    }
+    pub fn close_fee_split_config(ctx: Context<CloseFeeSplitConfig>) -> Result<()> {
+        close_fee_split_config::handler(ctx)?;
+        Ok(())
+    }
+    pub fn distribute_fees<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DistributeFees<'info>>,
+    ) -> Result<()> {
+        distribute_fees::handler(ctx)?;
+        Ok(())
+    }
+    pub fn create_trader_stats(ctx: Context<CreateTraderStats>) -> Result<()> {
+        create_trader_stats::handler(ctx)?;
+        Ok(())
+    }
     pub fn make_offer(ctx: Context<MakeOffer>, args: MakeOfferArgs) -> Result<()> {
         make_offer::handler(ctx, args)?;
         Ok(())
     }
-    pub fn take_offer(ctx: Context<TakeOffer>) -> Result<()> {
-        take_offer::handler(ctx)?;
+    pub fn take_offer(ctx: Context<TakeOffer>, args: TakeOfferArgs) -> Result<()> {
+        take_offer::handler(ctx, args)?;
         Ok(())
     }
     pub fn cancel_offer(ctx: Context<CancelOffer>) -> Result<()> {
         cancel_offer::handler(ctx)?;
         Ok(())
     }
+    pub fn update_offer(ctx: Context<UpdateOffer>, args: UpdateOfferArgs) -> Result<()> {
+        update_offer::handler(ctx, args)?;
+        Ok(())
+    }
+    pub fn reclaim_expired_offer(ctx: Context<ReclaimExpiredOffer>) -> Result<()> {
+        reclaim_expired_offer::handler(ctx)?;
+        Ok(())
+    }
+    pub fn make_bid(ctx: Context<MakeBid>, args: MakeBidArgs) -> Result<()> {
+        make_bid::handler(ctx, args)?;
+        Ok(())
+    }
+    pub fn take_bid(ctx: Context<TakeBid>, args: TakeBidArgs) -> Result<()> {
+        take_bid::handler(ctx, args)?;
+        Ok(())
+    }
+    pub fn cancel_bid(ctx: Context<CancelBid>) -> Result<()> {
+        cancel_bid::handler(ctx)?;
+        Ok(())
+    }
+    pub fn reclaim_expired_bid(ctx: Context<ReclaimExpiredBid>) -> Result<()> {
+        reclaim_expired_bid::handler(ctx)?;
+        Ok(())
+    }
+    pub fn match_offers(ctx: Context<MatchOffers>) -> Result<()> {
+        match_offers::handler(ctx)?;
+        Ok(())
+    }
+    pub fn make_basket_offer<'info>(
+        ctx: Context<'_, '_, 'info, 'info, MakeBasketOffer<'info>>,
+        args: MakeBasketOfferArgs,
+    ) -> Result<()> {
+        make_basket_offer::handler(ctx, args)?;
+        Ok(())
+    }
+    pub fn take_basket_offer<'info>(
+        ctx: Context<'_, '_, 'info, 'info, TakeBasketOffer<'info>>,
+        args: TakeBasketOfferArgs,
+    ) -> Result<()> {
+        take_basket_offer::handler(ctx, args)?;
+        Ok(())
+    }
+    pub fn cancel_basket_offer<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CancelBasketOffer<'info>>,
+    ) -> Result<()> {
+        cancel_basket_offer::handler(ctx)?;
+        Ok(())
+    }
+    pub fn reclaim_expired_basket_offer<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ReclaimExpiredBasketOffer<'info>>,
+    ) -> Result<()> {
+        reclaim_expired_basket_offer::handler(ctx)?;
+        Ok(())
+    }
+    pub fn create_auction(ctx: Context<CreateAuction>, args: CreateAuctionArgs) -> Result<()> {
+        create_auction::handler(ctx, args)?;
+        Ok(())
+    }
+    pub fn place_bid(ctx: Context<PlaceBid>, args: PlaceBidArgs) -> Result<()> {
+        place_bid::handler(ctx, args)?;
+        Ok(())
+    }
+    pub fn settle_auction(ctx: Context<SettleAuction>) -> Result<()> {
+        settle_auction::handler(ctx)?;
+        Ok(())
+    }
+    pub fn withdraw_bid_refund(ctx: Context<WithdrawBidRefund>) -> Result<()> {
+        withdraw_bid_refund::handler(ctx)?;
+        Ok(())
+    }
 }
@@ -1,6 +1,43 @@
 use crate::consts::MAX_BPS_VALUE;
 use crate::errors::EscrowError;
 use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace, Debug, PartialEq, Eq,
+)]
+pub enum MintListMode {
+    /// Every mint can be traded, the mint configs are not enforced
+    #[default]
+    Open,
+    /// Only mints with an allowed `MintConfig` can be traded
+    Allowlist,
+    /// Every mint can be traded unless its `MintConfig` denies it
+    Denylist,
+}
+
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace, Debug, PartialEq, Eq,
+)]
+pub enum FeeRoundingMode {
+    /// Fractional fees are rounded down
+    #[default]
+    Floor,
+    /// Fractional fees are rounded up
+    Ceil,
+    /// Fractional fees of half a unit or more are rounded up
+    HalfUp,
+}
+
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace, Debug, PartialEq, Eq,
+)]
+pub struct FeeTier {
+    /// Cumulative volume from which the tier applies, in the unit pairs convert fills into
+    pub volume_threshold: u64,
+    /// Share of the fee waived for traders in the tier
+    pub fee_discount_bps: u16,
+}
 
 #[account]
 #[derive(Default, InitSpace, Debug)]
@@ -9,10 +46,21 @@ pub struct EscrowState {
     pub maker_fee_bps: u16,
     pub taker_fee_bps: u16,
     pub bump: u8,
+    // Fields below were added after the initial deployment. New fields must be appended
+    // and be valid when zeroed, see `migrate_escrow_state`.
+    pub pending_manager: Option<Pubkey>,
+    pub make_paused: bool,
+    pub take_paused: bool,
+    pub mint_list_mode: MintListMode,
+    pub fee_rounding_mode: FeeRoundingMode,
+    pub referral_share_bps: u16,
+    #[max_len(8)]
+    pub fee_tiers: Vec<FeeTier>,
 }
 
 impl EscrowState {
     pub const SEED: &'static [u8] = b"state";
+    pub const MAX_FEE_TIERS: usize = 8;
 
     pub fn write(
         escrow_state: &mut Account<'_, EscrowState>,
@@ -21,40 +69,632 @@ impl EscrowState {
         taker_fee_bps: Option<u16>,
         bump: Option<u8>,
     ) -> Result<Self> {
-        manager.map(|m| escrow_state.manager = m);
-        maker_fee_bps.map(|fee| escrow_state.maker_fee_bps = fee);
-        taker_fee_bps.map(|fee| escrow_state.taker_fee_bps = fee);
-        bump.map(|b| escrow_state.bump = b);
+        if let Some(m) = manager {
+            escrow_state.manager = m;
+        }
+        if let Some(fee) = maker_fee_bps {
+            escrow_state.maker_fee_bps = fee;
+        }
+        if let Some(fee) = taker_fee_bps {
+            escrow_state.taker_fee_bps = fee;
+        }
+        if let Some(b) = bump {
+            escrow_state.bump = b;
+        }
 
         Ok(Self {
             manager: escrow_state.manager,
             maker_fee_bps: escrow_state.maker_fee_bps,
             taker_fee_bps: escrow_state.taker_fee_bps,
             bump: escrow_state.bump,
+            pending_manager: escrow_state.pending_manager,
+            make_paused: escrow_state.make_paused,
+            take_paused: escrow_state.take_paused,
+            mint_list_mode: escrow_state.mint_list_mode,
+            fee_rounding_mode: escrow_state.fee_rounding_mode,
+            referral_share_bps: escrow_state.referral_share_bps,
+            fee_tiers: escrow_state.fee_tiers.clone(),
         })
     }
 
-    /// Calculate token A (offer token) fee amount.
+    /// Check that `data` is an escrow state managed by `manager`, written with an older
+    /// layout than the current one.
+    pub fn assert_is_migratable(data: &[u8], manager: &Pubkey) -> Result<()> {
+        if data.len() < 8 + 32 || data[..8] != Self::DISCRIMINATOR {
+            return Err(EscrowError::EscrowStateKeyMismatch.into());
+        }
+
+        // The manager is the first field in every layout
+        if data[8..8 + 32] != manager.to_bytes() {
+            return Err(EscrowError::ManagerKeyUnauthorized.into());
+        }
+
+        if data.len() >= 8 + Self::INIT_SPACE {
+            return Err(EscrowError::EscrowStateAlreadyMigrated.into());
+        }
+
+        Ok(())
+    }
+
+    /// Check whether a mint can be traded under the configured mint list mode.
+    pub fn is_mint_permitted(&self, mint_config: Option<&MintConfig>) -> bool {
+        match self.mint_list_mode {
+            MintListMode::Open => true,
+            MintListMode::Allowlist => {
+                mint_config.is_some_and(|config| config.allowed && !config.denied)
+            }
+            MintListMode::Denylist => !mint_config.is_some_and(|config| config.denied),
+        }
+    }
+
+    /// Resolve the maker and taker fee bps, preferring the trading pair's override.
+    pub fn get_fee_bps(&self, pair_fee_config: Option<&PairFeeConfig>) -> (u16, u16) {
+        match pair_fee_config {
+            Some(config) => (config.maker_fee_bps, config.taker_fee_bps),
+            None => (self.maker_fee_bps, self.taker_fee_bps),
+        }
+    }
+
+    /// Resolve the fee discount of the highest fee tier reached by `volume`.
+    pub fn get_fee_discount_bps(&self, volume: u64) -> u16 {
+        self.fee_tiers
+            .iter()
+            .filter(|tier| volume >= tier.volume_threshold)
+            .map(|tier| tier.fee_discount_bps)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Resolve the taker fee bps after the pair override and the taker's fee tier.
+    pub fn get_taker_fee_bps(
+        &self,
+        pair_fee_config: Option<&PairFeeConfig>,
+        taker_stats: Option<&TraderStats>,
+    ) -> Result<u16> {
+        let (_, taker_fee_bps) = self.get_fee_bps(pair_fee_config);
+        let taker_volume = taker_stats.map_or(0, |stats| stats.taker_volume);
+
+        Self::apply_fee_discount(taker_fee_bps, self.get_fee_discount_bps(taker_volume))
+    }
+
+    /// Resolve the maker fee bps after the pair override and the maker's fee tier.
+    pub fn get_maker_fee_bps(
+        &self,
+        pair_fee_config: Option<&PairFeeConfig>,
+        maker_stats: Option<&TraderStats>,
+    ) -> Result<u16> {
+        let (maker_fee_bps, _) = self.get_fee_bps(pair_fee_config);
+        let maker_volume = maker_stats.map_or(0, |stats| stats.maker_volume);
+
+        Self::apply_fee_discount(maker_fee_bps, self.get_fee_discount_bps(maker_volume))
+    }
+
+    /// Calculate the fee levied on the token received by the **taker** of a trade.
     ///
-    /// The fee is to be levied **from the amount transferred from vault to taker**.
-    pub fn get_token_a_fee(&self, amount: u64) -> Result<u64> {
-        u128::from(amount)
-            .checked_mul(u128::from(self.taker_fee_bps))
-            .and_then(|v| v.checked_div(u128::from(MAX_BPS_VALUE)))
-            .and_then(|v| u64::try_from(v).ok())
-            .ok_or(EscrowError::MathError.into())
+    /// The fee is discounted by the fee tier of the taker's volume.
+    pub fn get_taker_fee(
+        &self,
+        amount: u64,
+        pair_fee_config: Option<&PairFeeConfig>,
+        mint_config: Option<&MintConfig>,
+        taker_stats: Option<&TraderStats>,
+    ) -> Result<u64> {
+        let taker_fee_bps = self.get_taker_fee_bps(pair_fee_config, taker_stats)?;
+
+        self.calculate_fee(amount, taker_fee_bps, mint_config)
     }
 
-    /// Calculate token B (ask token) fee amount.
+    /// Calculate the fee levied on the token received by the **maker** of a trade.
     ///
-    /// The fee is to be levied **from the amount transferred from taker to maker**.
-    pub fn get_token_b_fee(&self, amount: u64) -> Result<u64> {
-        u128::from(amount)
-            .checked_mul(u128::from(self.maker_fee_bps))
+    /// The fee is discounted by the fee tier of the maker's volume.
+    pub fn get_maker_fee(
+        &self,
+        amount: u64,
+        pair_fee_config: Option<&PairFeeConfig>,
+        mint_config: Option<&MintConfig>,
+        maker_stats: Option<&TraderStats>,
+    ) -> Result<u64> {
+        let maker_fee_bps = self.get_maker_fee_bps(pair_fee_config, maker_stats)?;
+
+        self.calculate_fee(amount, maker_fee_bps, mint_config)
+    }
+
+    /// Calculate token A (offer token) fee amount from a **token A amount**.
+    ///
+    /// The fee is to be levied **from the amount transferred from vault to taker**, and is
+    /// discounted by the fee tier of the taker's volume.
+    pub fn get_token_a_fee(
+        &self,
+        token_a_amount: u64,
+        pair_fee_config: Option<&PairFeeConfig>,
+        token_a_mint_config: Option<&MintConfig>,
+        taker_stats: Option<&TraderStats>,
+    ) -> Result<u64> {
+        self.get_taker_fee(
+            token_a_amount,
+            pair_fee_config,
+            token_a_mint_config,
+            taker_stats,
+        )
+    }
+
+    /// Calculate token B (ask token) fee amount from a **token B amount**.
+    ///
+    /// The fee is to be levied **from the amount transferred from taker to maker**, and is
+    /// discounted by the fee tier of the maker's volume.
+    pub fn get_token_b_fee(
+        &self,
+        token_b_amount: u64,
+        pair_fee_config: Option<&PairFeeConfig>,
+        token_b_mint_config: Option<&MintConfig>,
+        maker_stats: Option<&TraderStats>,
+    ) -> Result<u64> {
+        self.get_maker_fee(
+            token_b_amount,
+            pair_fee_config,
+            token_b_mint_config,
+            maker_stats,
+        )
+    }
+
+    fn apply_fee_discount(fee_bps: u16, fee_discount_bps: u16) -> Result<u16> {
+        u32::from(MAX_BPS_VALUE)
+            .checked_sub(u32::from(fee_discount_bps))
+            .and_then(|v| v.checked_mul(u32::from(fee_bps)))
+            .and_then(|v| v.checked_div(u32::from(MAX_BPS_VALUE)))
+            .and_then(|v| u16::try_from(v).ok())
+            .ok_or(EscrowError::MathError.into())
+    }
+
+    /// Calculate the part of a collected fee which is paid to the referrer of a trade.
+    pub fn get_referral_amount(&self, fee_amount: u64) -> Result<u64> {
+        u128::from(fee_amount)
+            .checked_mul(u128::from(self.referral_share_bps))
             .and_then(|v| v.checked_div(u128::from(MAX_BPS_VALUE)))
             .and_then(|v| u64::try_from(v).ok())
             .ok_or(EscrowError::MathError.into())
     }
+
+    /// Apply `fee_bps` to `amount` with the configured rounding mode, then raise the fee to
+    /// the mint's minimum fee. The fee never exceeds `amount`, and a zero bps fee stays zero.
+    fn calculate_fee(
+        &self,
+        amount: u64,
+        fee_bps: u16,
+        mint_config: Option<&MintConfig>,
+    ) -> Result<u64> {
+        if fee_bps == 0 {
+            return Ok(0);
+        }
+
+        let max_bps_value = u128::from(MAX_BPS_VALUE);
+        let rounding = match self.fee_rounding_mode {
+            FeeRoundingMode::Floor => 0,
+            FeeRoundingMode::Ceil => max_bps_value - 1,
+            FeeRoundingMode::HalfUp => max_bps_value / 2,
+        };
+
+        let fee_amount = u128::from(amount)
+            .checked_mul(u128::from(fee_bps))
+            .and_then(|v| v.checked_add(rounding))
+            .and_then(|v| v.checked_div(max_bps_value))
+            .and_then(|v| u64::try_from(v).ok())
+            .ok_or(EscrowError::MathError)?;
+
+        let min_fee_amount = mint_config.map_or(0, |config| config.min_fee_amount);
+
+        Ok(fee_amount.max(min_fee_amount).min(amount))
+    }
+}
+
+#[cfg(test)]
+mod escrow_state_tests {
+    use super::*;
+
+    fn escrow_state(maker_fee_bps: u16, taker_fee_bps: u16) -> EscrowState {
+        EscrowState {
+            maker_fee_bps,
+            taker_fee_bps,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn baseline_escrow_state_migrates_with_new_fields_zeroed() {
+        let manager = Pubkey::new_unique();
+
+        // Escrow state as written by the initial deployment
+        let mut data = EscrowState::DISCRIMINATOR.to_vec();
+        (manager, 25u16, 50u16, 254u8).serialize(&mut data).unwrap();
+
+        assert!(EscrowState::assert_is_migratable(&data, &manager).is_ok());
+        assert!(EscrowState::assert_is_migratable(&data, &Pubkey::new_unique()).is_err());
+
+        // `migrate_escrow_state` reallocs with zero-init
+        data.resize(8 + EscrowState::INIT_SPACE, 0);
+
+        let state = EscrowState::try_deserialize(&mut &data[..]).unwrap();
+
+        assert_eq!(state.manager, manager);
+        assert_eq!(state.maker_fee_bps, 25);
+        assert_eq!(state.taker_fee_bps, 50);
+        assert_eq!(state.bump, 254);
+        assert_eq!(state.pending_manager, None);
+        assert!(!state.make_paused);
+        assert!(!state.take_paused);
+        assert_eq!(state.mint_list_mode, MintListMode::default());
+        assert_eq!(state.fee_rounding_mode, FeeRoundingMode::default());
+        assert_eq!(state.referral_share_bps, 0);
+        assert!(state.fee_tiers.is_empty());
+
+        assert!(EscrowState::assert_is_migratable(&data, &manager).is_err());
+    }
+
+    #[test]
+    fn token_a_fee_uses_taker_fee_bps() {
+        let state = escrow_state(200, 100);
+
+        assert_eq!(
+            state.get_token_a_fee(1_000_000, None, None, None).unwrap(),
+            10_000
+        );
+    }
+
+    #[test]
+    fn token_b_fee_uses_maker_fee_bps() {
+        let state = escrow_state(200, 100);
+
+        assert_eq!(
+            state.get_token_b_fee(1_000_000, None, None, None).unwrap(),
+            20_000
+        );
+    }
+
+    #[test]
+    fn each_leg_fee_follows_its_own_decimals() {
+        let state = escrow_state(100, 100);
+
+        // 1_000 token A with 9 decimals offered for 2_000 token B with 6 decimals
+        let token_a_amount = 1_000 * 10u64.pow(9);
+        let token_b_amount = 2_000 * 10u64.pow(6);
+
+        assert_eq!(
+            state
+                .get_token_a_fee(token_a_amount, None, None, None)
+                .unwrap(),
+            10 * 10u64.pow(9)
+        );
+        assert_eq!(
+            state
+                .get_token_b_fee(token_b_amount, None, None, None)
+                .unwrap(),
+            20 * 10u64.pow(6)
+        );
+    }
+
+    #[test]
+    fn fee_at_max_bps_is_the_whole_amount() {
+        let state = escrow_state(MAX_BPS_VALUE, MAX_BPS_VALUE);
+
+        assert_eq!(
+            state.get_token_a_fee(123_456, None, None, None).unwrap(),
+            123_456
+        );
+        assert_eq!(
+            state.get_token_b_fee(654_321, None, None, None).unwrap(),
+            654_321
+        );
+    }
+
+    #[test]
+    fn fee_at_max_bps_does_not_overflow() {
+        let state = escrow_state(MAX_BPS_VALUE, MAX_BPS_VALUE);
+
+        assert_eq!(
+            state.get_token_a_fee(u64::MAX, None, None, None).unwrap(),
+            u64::MAX
+        );
+        assert_eq!(
+            state.get_token_b_fee(u64::MAX, None, None, None).unwrap(),
+            u64::MAX
+        );
+    }
+
+    #[test]
+    fn fee_is_zero_at_zero_bps() {
+        let state = escrow_state(0, 0);
+
+        assert_eq!(
+            state.get_token_a_fee(u64::MAX, None, None, None).unwrap(),
+            0
+        );
+        assert_eq!(
+            state.get_token_b_fee(u64::MAX, None, None, None).unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn fee_is_floored() {
+        let state = escrow_state(100, 100);
+
+        assert_eq!(state.get_token_a_fee(199, None, None, None).unwrap(), 1);
+        assert_eq!(state.get_token_b_fee(99, None, None, None).unwrap(), 0);
+    }
+
+    #[test]
+    fn fee_is_rounded_up_in_ceil_mode() {
+        let state = EscrowState {
+            fee_rounding_mode: FeeRoundingMode::Ceil,
+            ..escrow_state(100, 100)
+        };
+
+        assert_eq!(state.get_token_a_fee(1, None, None, None).unwrap(), 1);
+        assert_eq!(state.get_token_a_fee(100, None, None, None).unwrap(), 1);
+        assert_eq!(state.get_token_b_fee(101, None, None, None).unwrap(), 2);
+    }
+
+    #[test]
+    fn fee_is_rounded_half_up_in_half_up_mode() {
+        let state = EscrowState {
+            fee_rounding_mode: FeeRoundingMode::HalfUp,
+            ..escrow_state(100, 100)
+        };
+
+        assert_eq!(state.get_token_a_fee(49, None, None, None).unwrap(), 0);
+        assert_eq!(state.get_token_a_fee(50, None, None, None).unwrap(), 1);
+        assert_eq!(state.get_token_b_fee(149, None, None, None).unwrap(), 1);
+        assert_eq!(state.get_token_b_fee(150, None, None, None).unwrap(), 2);
+    }
+
+    #[test]
+    fn rounding_does_not_overflow_at_max_bps() {
+        for fee_rounding_mode in [
+            FeeRoundingMode::Floor,
+            FeeRoundingMode::Ceil,
+            FeeRoundingMode::HalfUp,
+        ] {
+            let state = EscrowState {
+                fee_rounding_mode,
+                ..escrow_state(MAX_BPS_VALUE, MAX_BPS_VALUE)
+            };
+
+            assert_eq!(
+                state.get_token_a_fee(u64::MAX, None, None, None).unwrap(),
+                u64::MAX
+            );
+        }
+    }
+
+    #[test]
+    fn min_fee_amount_raises_dust_fees() {
+        let state = escrow_state(100, 100);
+        let mint_config = MintConfig {
+            min_fee_amount: 5,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            state
+                .get_token_a_fee(10, None, Some(&mint_config), None)
+                .unwrap(),
+            5
+        );
+        assert_eq!(
+            state
+                .get_token_b_fee(1_000_000, None, Some(&mint_config), None)
+                .unwrap(),
+            10_000
+        );
+    }
+
+    #[test]
+    fn min_fee_amount_is_capped_at_the_amount() {
+        let state = escrow_state(100, 100);
+        let mint_config = MintConfig {
+            min_fee_amount: 5,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            state
+                .get_token_a_fee(3, None, Some(&mint_config), None)
+                .unwrap(),
+            3
+        );
+    }
+
+    #[test]
+    fn min_fee_amount_is_not_applied_at_zero_bps() {
+        let state = escrow_state(0, 0);
+        let mint_config = MintConfig {
+            min_fee_amount: 5,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            state
+                .get_token_b_fee(10, None, Some(&mint_config), None)
+                .unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn referral_amount_is_a_share_of_the_fee() {
+        let state = EscrowState {
+            referral_share_bps: 2_500,
+            ..escrow_state(100, 100)
+        };
+
+        assert_eq!(state.get_referral_amount(10_000).unwrap(), 2_500);
+        assert_eq!(state.get_referral_amount(3).unwrap(), 0);
+        assert_eq!(state.get_referral_amount(0).unwrap(), 0);
+    }
+
+    #[test]
+    fn referral_amount_at_max_bps_is_the_whole_fee() {
+        let state = EscrowState {
+            referral_share_bps: MAX_BPS_VALUE,
+            ..escrow_state(100, 100)
+        };
+
+        assert_eq!(state.get_referral_amount(u64::MAX).unwrap(), u64::MAX);
+    }
+
+    #[test]
+    fn pair_fee_config_overrides_global_fees() {
+        let state = escrow_state(100, 100);
+        let pair_fee_config = PairFeeConfig {
+            maker_fee_bps: 10,
+            taker_fee_bps: 5,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            state
+                .get_token_a_fee(1_000_000, Some(&pair_fee_config), None, None)
+                .unwrap(),
+            500
+        );
+        assert_eq!(
+            state
+                .get_token_b_fee(1_000_000, Some(&pair_fee_config), None, None)
+                .unwrap(),
+            1_000
+        );
+    }
+
+    const FEE_TIERS: [FeeTier; 2] = [
+        FeeTier {
+            volume_threshold: 1_000,
+            fee_discount_bps: 2_500,
+        },
+        FeeTier {
+            volume_threshold: 10_000,
+            fee_discount_bps: 5_000,
+        },
+    ];
+
+    #[test]
+    fn fee_discount_follows_the_highest_tier_reached() {
+        let state = EscrowState {
+            fee_tiers: FEE_TIERS.to_vec(),
+            ..escrow_state(100, 200)
+        };
+
+        assert_eq!(state.get_fee_discount_bps(0), 0);
+        assert_eq!(state.get_fee_discount_bps(999), 0);
+        assert_eq!(state.get_fee_discount_bps(1_000), 2_500);
+        assert_eq!(state.get_fee_discount_bps(u64::MAX), 5_000);
+    }
+
+    #[test]
+    fn fee_tiers_discount_each_side_by_its_own_volume() {
+        let state = EscrowState {
+            fee_tiers: FEE_TIERS.to_vec(),
+            ..escrow_state(100, 200)
+        };
+        let trader_stats = TraderStats {
+            maker_volume: 10_000,
+            taker_volume: 1_000,
+            ..Default::default()
+        };
+
+        // taker: 200 bps discounted by 25%
+        assert_eq!(
+            state
+                .get_token_a_fee(1_000_000, None, None, Some(&trader_stats))
+                .unwrap(),
+            15_000
+        );
+        // maker: 100 bps discounted by 50%
+        assert_eq!(
+            state
+                .get_token_b_fee(1_000_000, None, None, Some(&trader_stats))
+                .unwrap(),
+            5_000
+        );
+    }
+
+    #[test]
+    fn effective_fee_bps_apply_pair_override_and_tier_discount() {
+        let state = EscrowState {
+            fee_tiers: FEE_TIERS.to_vec(),
+            ..escrow_state(100, 200)
+        };
+        let pair_fee_config = PairFeeConfig {
+            maker_fee_bps: 40,
+            taker_fee_bps: 80,
+            ..Default::default()
+        };
+        let trader_stats = TraderStats {
+            maker_volume: 10_000,
+            taker_volume: 1_000,
+            ..Default::default()
+        };
+
+        assert_eq!(state.get_taker_fee_bps(None, None).unwrap(), 200);
+        assert_eq!(
+            state
+                .get_taker_fee_bps(Some(&pair_fee_config), Some(&trader_stats))
+                .unwrap(),
+            60
+        );
+        assert_eq!(
+            state
+                .get_maker_fee_bps(Some(&pair_fee_config), Some(&trader_stats))
+                .unwrap(),
+            20
+        );
+    }
+
+    #[test]
+    fn full_fee_discount_waives_the_fee() {
+        let state = EscrowState {
+            fee_tiers: vec![FeeTier {
+                volume_threshold: 0,
+                fee_discount_bps: MAX_BPS_VALUE,
+            }],
+            ..escrow_state(MAX_BPS_VALUE, MAX_BPS_VALUE)
+        };
+
+        assert_eq!(
+            state.get_token_a_fee(u64::MAX, None, None, None).unwrap(),
+            0
+        );
+        assert_eq!(
+            state.get_token_b_fee(u64::MAX, None, None, None).unwrap(),
+            0
+        );
+    }
+}
+
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace, Debug, PartialEq, Eq,
+)]
+pub enum OfferKind {
+    /// The offer asks a fixed `token_b_wanted_amount`
+    #[default]
+    Fixed,
+    /// The ask decays linearly from `token_b_wanted_amount` at `start_time` down to
+    /// `end_token_b_wanted_amount` at `end_time`
+    DutchAuction {
+        end_token_b_wanted_amount: u64,
+        start_time: i64,
+        end_time: i64,
+    },
+}
+
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace, Debug, PartialEq, Eq,
+)]
+pub enum OfferSide {
+    /// The maker escrows token A and asks for token B
+    #[default]
+    Ask,
+    /// The maker escrows token B and bids for token A
+    Bid,
 }
 
 #[account]
@@ -64,36 +704,1203 @@ pub struct Offer {
     pub maker: Pubkey,
     pub token_mint_a: Pubkey,
     pub token_mint_b: Pubkey,
+    /// Token A escrowed by an ask, or token A wanted by a bid
+    pub token_a_offered_amount: u64,
+    /// Token B wanted by an ask, or token B escrowed by a bid
     pub token_b_wanted_amount: u64,
+    pub token_a_filled_amount: u64,
+    pub token_b_filled_amount: u64,
+    pub funder: Pubkey,
+    pub created_at: i64,
+    pub expires_at: Option<i64>,
+    pub allowed_taker: Option<Pubkey>,
+    pub taker_merkle_root: Option<[u8; 32]>,
+    pub kind: OfferKind,
+    pub side: OfferSide,
     pub bump: u8,
 }
 
 impl Offer {
     pub const SEED_PREFIX: &'static [u8] = b"offer";
 
+    #[allow(clippy::too_many_arguments)]
     pub fn write(
         offer: &mut Account<'_, Offer>,
         id: Option<u64>,
         maker: Option<Pubkey>,
         token_mint_a: Option<Pubkey>,
         token_mint_b: Option<Pubkey>,
+        token_a_offered_amount: Option<u64>,
         token_b_wanted_amount: Option<u64>,
         bump: Option<u8>,
     ) -> Result<Self> {
-        id.map(|i| offer.id = i);
-        maker.map(|m| offer.maker = m);
-        token_mint_a.map(|tma| offer.token_mint_a = tma);
-        token_mint_b.map(|tmb| offer.token_mint_b = tmb);
-        token_b_wanted_amount.map(|tbwa| offer.token_b_wanted_amount = tbwa);
-        bump.map(|b| offer.bump = b);
+        if let Some(i) = id {
+            offer.id = i;
+        }
+        if let Some(m) = maker {
+            offer.maker = m;
+        }
+        if let Some(tma) = token_mint_a {
+            offer.token_mint_a = tma;
+        }
+        if let Some(tmb) = token_mint_b {
+            offer.token_mint_b = tmb;
+        }
+        if let Some(taoa) = token_a_offered_amount {
+            offer.token_a_offered_amount = taoa;
+        }
+        if let Some(tbwa) = token_b_wanted_amount {
+            offer.token_b_wanted_amount = tbwa;
+        }
+        if let Some(b) = bump {
+            offer.bump = b;
+        }
 
         Ok(Self {
             id: offer.id,
             maker: offer.maker,
             token_mint_a: offer.token_mint_a,
             token_mint_b: offer.token_mint_b,
+            token_a_offered_amount: offer.token_a_offered_amount,
             token_b_wanted_amount: offer.token_b_wanted_amount,
+            token_a_filled_amount: offer.token_a_filled_amount,
+            token_b_filled_amount: offer.token_b_filled_amount,
+            funder: offer.funder,
+            created_at: offer.created_at,
+            expires_at: offer.expires_at,
+            allowed_taker: offer.allowed_taker,
+            taker_merkle_root: offer.taker_merkle_root,
+            kind: offer.kind,
+            side: offer.side,
             bump: offer.bump,
         })
     }
+
+    pub fn token_a_remaining_amount(&self) -> Result<u64> {
+        self.token_a_offered_amount
+            .checked_sub(self.token_a_filled_amount)
+            .ok_or(EscrowError::MathError.into())
+    }
+
+    pub fn token_b_remaining_amount(&self) -> Result<u64> {
+        self.token_b_wanted_amount
+            .checked_sub(self.token_b_filled_amount)
+            .ok_or(EscrowError::MathError.into())
+    }
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        matches!(self.expires_at, Some(expires_at) if now >= expires_at)
+    }
+
+    /// Private offers can only be taken by their designated taker.
+    pub fn is_taker_allowed(&self, taker: &Pubkey) -> bool {
+        match self.allowed_taker {
+            Some(allowed_taker) => allowed_taker == *taker,
+            None => true,
+        }
+    }
+
+    pub fn is_filled(&self) -> Result<bool> {
+        Ok(self.token_a_remaining_amount()? == 0)
+    }
+
+    /// Check that both sides of the offer are non-zero, since an offer without token A or
+    /// token B could never be filled.
+    pub fn assert_is_valid_amounts(&self) -> Result<()> {
+        if self.token_a_offered_amount == 0 || self.token_b_wanted_amount == 0 {
+            return err!(EscrowError::InvalidOfferAmount);
+        }
+
+        Ok(())
+    }
+
+    /// Check that the auction parameters of a Dutch auction offer describe a decaying price.
+    pub fn assert_is_valid_kind(&self) -> Result<()> {
+        if let OfferKind::DutchAuction {
+            end_token_b_wanted_amount,
+            start_time,
+            end_time,
+        } = self.kind
+        {
+            if start_time >= end_time || end_token_b_wanted_amount > self.token_b_wanted_amount {
+                return err!(EscrowError::InvalidAuction);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Resolve the token B asked for the whole `token_a_offered_amount` at `now`.
+    ///
+    /// Dutch auctions ask the start amount until `start_time` and the end amount from
+    /// `end_time` on, decaying linearly in between.
+    pub fn get_token_b_wanted_amount(&self, now: i64) -> Result<u64> {
+        match self.kind {
+            OfferKind::Fixed => Ok(self.token_b_wanted_amount),
+            OfferKind::DutchAuction {
+                end_token_b_wanted_amount,
+                start_time,
+                end_time,
+            } => {
+                if now <= start_time {
+                    return Ok(self.token_b_wanted_amount);
+                }
+                if now >= end_time {
+                    return Ok(end_token_b_wanted_amount);
+                }
+
+                let decay = self
+                    .token_b_wanted_amount
+                    .checked_sub(end_token_b_wanted_amount)
+                    .and_then(|range| {
+                        u128::from(range).checked_mul(u128::try_from(now - start_time).ok()?)
+                    })
+                    .and_then(|v| v.checked_div(u128::try_from(end_time - start_time).ok()?))
+                    .and_then(|v| u64::try_from(v).ok())
+                    .ok_or(EscrowError::MathError)?;
+
+                self.token_b_wanted_amount
+                    .checked_sub(decay)
+                    .ok_or(EscrowError::MathError.into())
+            }
+        }
+    }
+
+    /// Calculate the token B amount exchanged for `token_a_amount` of the remaining token A.
+    ///
+    /// The pro-rata share is **rounded up** for asks and **rounded down** for bids, so a taker
+    /// can never receive either token for free. Taking all of the remaining token A of a
+    /// fixed offer always settles exactly the remaining token B. Dutch auctions price every
+    /// fill at the current ask instead.
+    pub fn get_token_b_amount_for_fill(&self, token_a_amount: u64, now: i64) -> Result<u64> {
+        let token_a_remaining_amount = self.token_a_remaining_amount()?;
+
+        if token_a_amount == 0 || token_a_amount > token_a_remaining_amount {
+            return err!(EscrowError::InvalidFillAmount);
+        }
+
+        let (token_a_base_amount, token_b_base_amount) = match self.kind {
+            OfferKind::Fixed => {
+                let token_b_remaining_amount = self.token_b_remaining_amount()?;
+
+                if token_a_amount == token_a_remaining_amount {
+                    return Ok(token_b_remaining_amount);
+                }
+
+                (token_a_remaining_amount, token_b_remaining_amount)
+            }
+            OfferKind::DutchAuction { .. } => (
+                self.token_a_offered_amount,
+                self.get_token_b_wanted_amount(now)?,
+            ),
+        };
+
+        let rounding = match self.side {
+            OfferSide::Ask => u128::from(token_a_base_amount) - 1,
+            OfferSide::Bid => 0,
+        };
+
+        u128::from(token_a_amount)
+            .checked_mul(u128::from(token_b_base_amount))
+            .and_then(|v| v.checked_add(rounding))
+            .and_then(|v| v.checked_div(u128::from(token_a_base_amount)))
+            .and_then(|v| u64::try_from(v).ok())
+            .ok_or(EscrowError::MathError.into())
+    }
+
+    /// Size a match between this ask and a crossing `bid` on the same pair at `now`.
+    ///
+    /// Returns the matched token A with the token B the ask is owed and the token B the bid
+    /// releases for it, each on the offer's own terms. The prices cross when the bid
+    /// releases at least what the ask is owed.
+    pub fn get_match_amounts(&self, bid: &Offer, now: i64) -> Result<(u64, u64, u64)> {
+        if self.side != OfferSide::Ask || bid.side != OfferSide::Bid {
+            return err!(EscrowError::OfferSideMismatch);
+        }
+
+        let token_a_amount = self
+            .token_a_remaining_amount()?
+            .min(bid.token_a_remaining_amount()?);
+
+        let ask_token_b_amount = self.get_token_b_amount_for_fill(token_a_amount, now)?;
+        let bid_token_b_amount = bid.get_token_b_amount_for_fill(token_a_amount, now)?;
+
+        if bid_token_b_amount < ask_token_b_amount {
+            return err!(EscrowError::PricesDoNotCross);
+        }
+
+        Ok((token_a_amount, ask_token_b_amount, bid_token_b_amount))
+    }
+
+    /// Record a fill of `token_a_amount` token A against `token_b_amount` token B.
+    pub fn record_fill(&mut self, token_a_amount: u64, token_b_amount: u64) -> Result<()> {
+        self.token_a_filled_amount = self
+            .token_a_filled_amount
+            .checked_add(token_a_amount)
+            .ok_or(EscrowError::MathError)?;
+        self.token_b_filled_amount = self
+            .token_b_filled_amount
+            .checked_add(token_b_amount)
+            .ok_or(EscrowError::MathError)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod offer_tests {
+    use super::*;
+
+    fn dutch_auction_offer() -> Offer {
+        Offer {
+            token_a_offered_amount: 1_000,
+            token_b_wanted_amount: 2_000,
+            kind: OfferKind::DutchAuction {
+                end_token_b_wanted_amount: 1_000,
+                start_time: 100,
+                end_time: 200,
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn dutch_auction_ask_decays_linearly() {
+        let offer = dutch_auction_offer();
+
+        assert_eq!(offer.get_token_b_wanted_amount(0).unwrap(), 2_000);
+        assert_eq!(offer.get_token_b_wanted_amount(100).unwrap(), 2_000);
+        assert_eq!(offer.get_token_b_wanted_amount(125).unwrap(), 1_750);
+        assert_eq!(offer.get_token_b_wanted_amount(150).unwrap(), 1_500);
+        assert_eq!(offer.get_token_b_wanted_amount(200).unwrap(), 1_000);
+        assert_eq!(offer.get_token_b_wanted_amount(i64::MAX).unwrap(), 1_000);
+    }
+
+    #[test]
+    fn dutch_auction_fills_are_priced_at_the_current_ask() {
+        let mut offer = dutch_auction_offer();
+
+        assert_eq!(offer.get_token_b_amount_for_fill(500, 100).unwrap(), 1_000);
+        offer.record_fill(500, 1_000).unwrap();
+
+        // The second half fills at the decayed ask rather than the remaining token B
+        assert_eq!(offer.get_token_b_amount_for_fill(500, 150).unwrap(), 750);
+        assert_eq!(offer.get_token_b_amount_for_fill(1, 150).unwrap(), 2);
+        assert!(offer.get_token_b_amount_for_fill(501, 150).is_err());
+    }
+
+    #[test]
+    fn dutch_auction_requires_a_decaying_price() {
+        let mut offer = dutch_auction_offer();
+        assert!(offer.assert_is_valid_kind().is_ok());
+
+        offer.kind = OfferKind::DutchAuction {
+            end_token_b_wanted_amount: 2_001,
+            start_time: 100,
+            end_time: 200,
+        };
+        assert!(offer.assert_is_valid_kind().is_err());
+
+        offer.kind = OfferKind::DutchAuction {
+            end_token_b_wanted_amount: 1_000,
+            start_time: 200,
+            end_time: 200,
+        };
+        assert!(offer.assert_is_valid_kind().is_err());
+    }
+
+    #[test]
+    fn offers_require_non_zero_amounts() {
+        let mut offer = Offer {
+            token_a_offered_amount: 1,
+            token_b_wanted_amount: 1,
+            ..Default::default()
+        };
+        assert!(offer.assert_is_valid_amounts().is_ok());
+
+        offer.token_a_offered_amount = 0;
+        assert!(offer.assert_is_valid_amounts().is_err());
+
+        offer.token_a_offered_amount = 1;
+        offer.token_b_wanted_amount = 0;
+        assert!(offer.assert_is_valid_amounts().is_err());
+    }
+
+    #[test]
+    fn fixed_offer_last_fill_settles_the_remaining_token_b() {
+        let mut offer = Offer {
+            token_a_offered_amount: 3,
+            token_b_wanted_amount: 10,
+            ..Default::default()
+        };
+
+        assert_eq!(offer.get_token_b_amount_for_fill(1, 0).unwrap(), 4);
+        offer.record_fill(1, 4).unwrap();
+        assert_eq!(offer.get_token_b_amount_for_fill(2, 0).unwrap(), 6);
+    }
+
+    #[test]
+    fn bid_offer_fills_round_down_and_settle_the_remaining_token_b() {
+        let mut offer = Offer {
+            token_a_offered_amount: 3,
+            token_b_wanted_amount: 10,
+            side: OfferSide::Bid,
+            ..Default::default()
+        };
+
+        assert_eq!(offer.get_token_b_amount_for_fill(1, 0).unwrap(), 3);
+        offer.record_fill(1, 3).unwrap();
+        assert_eq!(offer.get_token_b_amount_for_fill(1, 0).unwrap(), 3);
+        assert_eq!(offer.get_token_b_amount_for_fill(2, 0).unwrap(), 7);
+    }
+
+    #[test]
+    fn crossing_offers_match_the_smaller_remaining_size() {
+        let ask = Offer {
+            token_a_offered_amount: 100,
+            token_b_wanted_amount: 150,
+            ..Default::default()
+        };
+        let bid = Offer {
+            token_a_offered_amount: 40,
+            token_b_wanted_amount: 80,
+            side: OfferSide::Bid,
+            ..Default::default()
+        };
+
+        assert_eq!(ask.get_match_amounts(&bid, 0).unwrap(), (40, 60, 80));
+        assert!(bid.get_match_amounts(&ask, 0).is_err());
+    }
+
+    #[test]
+    fn offers_which_do_not_cross_are_not_matched() {
+        let ask = Offer {
+            token_a_offered_amount: 100,
+            token_b_wanted_amount: 200,
+            ..Default::default()
+        };
+        let bid = Offer {
+            token_a_offered_amount: 100,
+            token_b_wanted_amount: 199,
+            side: OfferSide::Bid,
+            ..Default::default()
+        };
+
+        assert!(ask.get_match_amounts(&bid, 0).is_err());
+    }
+}
+
+#[account]
+#[derive(Default, InitSpace, Debug)]
+pub struct Auction {
+    pub id: u64,
+    pub maker: Pubkey,
+    pub token_mint_a: Pubkey,
+    pub token_mint_b: Pubkey,
+    pub token_a_amount: u64,
+    /// Lowest token B accepted as the first bid
+    pub min_bid_amount: u64,
+    /// Token B every new bid must add on top of the highest bid
+    pub min_bid_increment: u64,
+    pub highest_bid_amount: u64,
+    pub highest_bidder: Option<Pubkey>,
+    /// Token B of outbid bids still held in the bid vault until their bidders withdraw it
+    pub pending_refund_amount: u64,
+    /// Set once the auction is settled while outbid bidders still have refunds to withdraw
+    pub settled: bool,
+    pub funder: Pubkey,
+    pub end_time: i64,
+    pub bump: u8,
+}
+
+impl Auction {
+    pub const SEED_PREFIX: &'static [u8] = b"auction";
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn write(
+        auction: &mut Account<'_, Auction>,
+        id: Option<u64>,
+        maker: Option<Pubkey>,
+        token_mint_a: Option<Pubkey>,
+        token_mint_b: Option<Pubkey>,
+        token_a_amount: Option<u64>,
+        end_time: Option<i64>,
+        bump: Option<u8>,
+    ) -> Result<Self> {
+        if let Some(i) = id {
+            auction.id = i;
+        }
+        if let Some(m) = maker {
+            auction.maker = m;
+        }
+        if let Some(tma) = token_mint_a {
+            auction.token_mint_a = tma;
+        }
+        if let Some(tmb) = token_mint_b {
+            auction.token_mint_b = tmb;
+        }
+        if let Some(taa) = token_a_amount {
+            auction.token_a_amount = taa;
+        }
+        if let Some(et) = end_time {
+            auction.end_time = et;
+        }
+        if let Some(b) = bump {
+            auction.bump = b;
+        }
+
+        Ok(Self {
+            id: auction.id,
+            maker: auction.maker,
+            token_mint_a: auction.token_mint_a,
+            token_mint_b: auction.token_mint_b,
+            token_a_amount: auction.token_a_amount,
+            min_bid_amount: auction.min_bid_amount,
+            min_bid_increment: auction.min_bid_increment,
+            highest_bid_amount: auction.highest_bid_amount,
+            highest_bidder: auction.highest_bidder,
+            pending_refund_amount: auction.pending_refund_amount,
+            settled: auction.settled,
+            funder: auction.funder,
+            end_time: auction.end_time,
+            bump: auction.bump,
+        })
+    }
+
+    pub fn is_ended(&self, now: i64) -> bool {
+        now >= self.end_time
+    }
+
+    /// Calculate the lowest token B amount the next bid must reach.
+    pub fn get_min_next_bid_amount(&self) -> Result<u64> {
+        match self.highest_bidder {
+            Some(_) => self
+                .highest_bid_amount
+                .checked_add(self.min_bid_increment)
+                .ok_or(EscrowError::MathError.into()),
+            None => Ok(self.min_bid_amount.max(1)),
+        }
+    }
+
+    /// Record `bid_amount` from `bidder` as the highest bid.
+    ///
+    /// The outbid amount stays in the bid vault as a pending refund until its bidder
+    /// withdraws it.
+    pub fn record_bid(&mut self, bidder: Pubkey, bid_amount: u64) -> Result<()> {
+        if bid_amount < self.get_min_next_bid_amount()? {
+            return err!(EscrowError::BidTooLow);
+        }
+
+        if self.highest_bidder.is_some() {
+            self.pending_refund_amount = self
+                .pending_refund_amount
+                .checked_add(self.highest_bid_amount)
+                .ok_or(EscrowError::MathError)?;
+        }
+
+        self.highest_bid_amount = bid_amount;
+        self.highest_bidder = Some(bidder);
+
+        Ok(())
+    }
+
+    /// Record `amount` withdrawn from the pending refunds.
+    pub fn record_refund_withdrawal(&mut self, amount: u64) -> Result<()> {
+        self.pending_refund_amount = self
+            .pending_refund_amount
+            .checked_sub(amount)
+            .ok_or(EscrowError::MathError)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod auction_tests {
+    use super::*;
+
+    #[test]
+    fn first_bid_must_reach_the_minimum_bid() {
+        let mut auction = Auction {
+            min_bid_amount: 100,
+            min_bid_increment: 10,
+            ..Default::default()
+        };
+
+        assert!(auction.record_bid(Pubkey::new_unique(), 99).is_err());
+        assert!(auction.record_bid(Pubkey::new_unique(), 100).is_ok());
+        assert_eq!(auction.highest_bid_amount, 100);
+    }
+
+    #[test]
+    fn next_bid_must_add_the_minimum_increment() {
+        let mut auction = Auction {
+            min_bid_amount: 100,
+            min_bid_increment: 10,
+            ..Default::default()
+        };
+        let bidder = Pubkey::new_unique();
+
+        auction.record_bid(Pubkey::new_unique(), 150).unwrap();
+
+        assert_eq!(auction.get_min_next_bid_amount().unwrap(), 160);
+        assert!(auction.record_bid(bidder, 159).is_err());
+        assert!(auction.record_bid(bidder, 160).is_ok());
+        assert_eq!(auction.highest_bidder, Some(bidder));
+    }
+
+    #[test]
+    fn outbid_amounts_are_held_as_pending_refunds() {
+        let mut auction = Auction {
+            min_bid_amount: 100,
+            min_bid_increment: 10,
+            ..Default::default()
+        };
+
+        auction.record_bid(Pubkey::new_unique(), 100).unwrap();
+        assert_eq!(auction.pending_refund_amount, 0);
+
+        auction.record_bid(Pubkey::new_unique(), 110).unwrap();
+        auction.record_bid(Pubkey::new_unique(), 150).unwrap();
+        assert_eq!(auction.pending_refund_amount, 210);
+
+        auction.record_refund_withdrawal(100).unwrap();
+        assert_eq!(auction.pending_refund_amount, 110);
+        assert!(auction.record_refund_withdrawal(111).is_err());
+    }
+
+    #[test]
+    fn zero_bids_are_rejected() {
+        let mut auction = Auction::default();
+
+        assert!(auction.record_bid(Pubkey::new_unique(), 0).is_err());
+        assert!(auction.record_bid(Pubkey::new_unique(), 1).is_ok());
+    }
+}
+
+#[account]
+#[derive(Default, InitSpace, Debug)]
+pub struct BidRefund {
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    /// Token B outbid on the auction and not yet withdrawn
+    pub amount: u64,
+    pub funder: Pubkey,
+    pub bump: u8,
+}
+
+impl BidRefund {
+    pub const SEED_PREFIX: &'static [u8] = b"bid_refund";
+
+    pub fn write(
+        bid_refund: &mut Account<'_, BidRefund>,
+        auction: Option<Pubkey>,
+        bidder: Option<Pubkey>,
+        funder: Option<Pubkey>,
+        bump: Option<u8>,
+    ) -> Result<Self> {
+        if let Some(a) = auction {
+            bid_refund.auction = a;
+        }
+        if let Some(b) = bidder {
+            bid_refund.bidder = b;
+        }
+        if let Some(f) = funder {
+            bid_refund.funder = f;
+        }
+        if let Some(b) = bump {
+            bid_refund.bump = b;
+        }
+
+        Ok(Self {
+            auction: bid_refund.auction,
+            bidder: bid_refund.bidder,
+            amount: bid_refund.amount,
+            funder: bid_refund.funder,
+            bump: bid_refund.bump,
+        })
+    }
+
+    /// Add `amount` outbid on the auction to the refund.
+    pub fn record_refund(&mut self, amount: u64) -> Result<()> {
+        self.amount = self
+            .amount
+            .checked_add(amount)
+            .ok_or(EscrowError::MathError)?;
+
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(Default, InitSpace, Debug)]
+pub struct MintConfig {
+    pub mint: Pubkey,
+    pub allowed: bool,
+    pub denied: bool,
+    pub min_fee_amount: u64,
+    #[max_len(64)]
+    pub notes: String,
+    pub bump: u8,
+}
+
+impl MintConfig {
+    pub const SEED_PREFIX: &'static [u8] = b"mint_config";
+    pub const MAX_NOTES_LEN: usize = 64;
+
+    pub fn write(
+        mint_config: &mut Account<'_, MintConfig>,
+        mint: Option<Pubkey>,
+        allowed: Option<bool>,
+        denied: Option<bool>,
+        min_fee_amount: Option<u64>,
+        notes: Option<String>,
+        bump: Option<u8>,
+    ) -> Result<Self> {
+        if let Some(m) = mint {
+            mint_config.mint = m;
+        }
+        if let Some(a) = allowed {
+            mint_config.allowed = a;
+        }
+        if let Some(d) = denied {
+            mint_config.denied = d;
+        }
+        if let Some(fee) = min_fee_amount {
+            mint_config.min_fee_amount = fee;
+        }
+        if let Some(n) = notes {
+            mint_config.notes = n;
+        }
+        if let Some(b) = bump {
+            mint_config.bump = b;
+        }
+
+        Ok(Self {
+            mint: mint_config.mint,
+            allowed: mint_config.allowed,
+            denied: mint_config.denied,
+            min_fee_amount: mint_config.min_fee_amount,
+            notes: mint_config.notes.clone(),
+            bump: mint_config.bump,
+        })
+    }
+}
+
+#[account]
+#[derive(Default, InitSpace, Debug)]
+pub struct PairFeeConfig {
+    pub token_mint_a: Pubkey,
+    pub token_mint_b: Pubkey,
+    pub maker_fee_bps: u16,
+    pub taker_fee_bps: u16,
+    /// Fee tier volume credited per whole token A filled on the pair, zero to not count it
+    pub volume_per_token_a: u64,
+    pub bump: u8,
+}
+
+impl PairFeeConfig {
+    pub const SEED_PREFIX: &'static [u8] = b"pair_fee";
+
+    pub fn write(
+        pair_fee_config: &mut Account<'_, PairFeeConfig>,
+        token_mint_a: Option<Pubkey>,
+        token_mint_b: Option<Pubkey>,
+        maker_fee_bps: Option<u16>,
+        taker_fee_bps: Option<u16>,
+        volume_per_token_a: Option<u64>,
+        bump: Option<u8>,
+    ) -> Result<Self> {
+        if let Some(tma) = token_mint_a {
+            pair_fee_config.token_mint_a = tma;
+        }
+        if let Some(tmb) = token_mint_b {
+            pair_fee_config.token_mint_b = tmb;
+        }
+        if let Some(fee) = maker_fee_bps {
+            pair_fee_config.maker_fee_bps = fee;
+        }
+        if let Some(fee) = taker_fee_bps {
+            pair_fee_config.taker_fee_bps = fee;
+        }
+        if let Some(v) = volume_per_token_a {
+            pair_fee_config.volume_per_token_a = v;
+        }
+        if let Some(b) = bump {
+            pair_fee_config.bump = b;
+        }
+
+        Ok(Self {
+            token_mint_a: pair_fee_config.token_mint_a,
+            token_mint_b: pair_fee_config.token_mint_b,
+            maker_fee_bps: pair_fee_config.maker_fee_bps,
+            taker_fee_bps: pair_fee_config.taker_fee_bps,
+            volume_per_token_a: pair_fee_config.volume_per_token_a,
+            bump: pair_fee_config.bump,
+        })
+    }
+
+    /// Convert a fill of `token_a_amount` into fee tier volume at the pair's rate.
+    pub fn get_fill_volume(&self, token_a_amount: u64, token_a_decimals: u8) -> u64 {
+        let volume = u128::from(token_a_amount)
+            .saturating_mul(u128::from(self.volume_per_token_a))
+            .checked_div(10u128.saturating_pow(u32::from(token_a_decimals)))
+            .unwrap_or(0);
+
+        u64::try_from(volume).unwrap_or(u64::MAX)
+    }
+}
+
+#[account]
+#[derive(Default, InitSpace, Debug)]
+pub struct Referrer {
+    /// Wallet the manager registered to receive referral shares
+    pub wallet: Pubkey,
+    pub bump: u8,
+}
+
+impl Referrer {
+    pub const SEED_PREFIX: &'static [u8] = b"referrer";
+
+    pub fn write(
+        referrer: &mut Account<'_, Referrer>,
+        wallet: Option<Pubkey>,
+        bump: Option<u8>,
+    ) -> Result<Self> {
+        if let Some(w) = wallet {
+            referrer.wallet = w;
+        }
+        if let Some(b) = bump {
+            referrer.bump = b;
+        }
+
+        Ok(Self {
+            wallet: referrer.wallet,
+            bump: referrer.bump,
+        })
+    }
+}
+
+#[account]
+#[derive(Default, InitSpace, Debug)]
+pub struct TraderStats {
+    pub trader: Pubkey,
+    /// Cumulative fee tier volume filled on the trader's offers
+    pub maker_volume: u64,
+    /// Cumulative fee tier volume taken by the trader
+    pub taker_volume: u64,
+    pub bump: u8,
+}
+
+impl TraderStats {
+    pub const SEED_PREFIX: &'static [u8] = b"trader_stats";
+
+    pub fn write(
+        trader_stats: &mut Account<'_, TraderStats>,
+        trader: Option<Pubkey>,
+        bump: Option<u8>,
+    ) -> Result<Self> {
+        if let Some(t) = trader {
+            trader_stats.trader = t;
+        }
+        if let Some(b) = bump {
+            trader_stats.bump = b;
+        }
+
+        Ok(Self {
+            trader: trader_stats.trader,
+            maker_volume: trader_stats.maker_volume,
+            taker_volume: trader_stats.taker_volume,
+            bump: trader_stats.bump,
+        })
+    }
+
+    /// Credit a fill of `token_a_amount` to the stats of its maker and taker, for the
+    /// traders which created their stats.
+    ///
+    /// Only pairs the manager configured with a volume rate count, otherwise volume could be
+    /// farmed by trading a worthless token between two wallets. The rate converts token A
+    /// into one unit shared by every pair, so tier thresholds mean the same whatever the
+    /// mint or its decimals. Volumes only drive fee tiers, so they saturate rather than fail
+    /// a trade.
+    pub fn record_fill(
+        pair_fee_config: Option<&PairFeeConfig>,
+        token_a_amount: u64,
+        token_a_decimals: u8,
+        maker_stats: Option<&mut TraderStats>,
+        taker_stats: Option<&mut TraderStats>,
+    ) {
+        let volume = pair_fee_config.map_or(0, |config| {
+            config.get_fill_volume(token_a_amount, token_a_decimals)
+        });
+
+        if let Some(stats) = maker_stats {
+            stats.maker_volume = stats.maker_volume.saturating_add(volume);
+        }
+        if let Some(stats) = taker_stats {
+            stats.taker_volume = stats.taker_volume.saturating_add(volume);
+        }
+    }
+}
+
+#[cfg(test)]
+mod trader_stats_tests {
+    use super::*;
+
+    #[test]
+    fn fills_record_volume_at_the_pair_rate_whatever_the_decimals() {
+        let pair_fee_config = PairFeeConfig {
+            volume_per_token_a: 2_000_000,
+            ..Default::default()
+        };
+        let mut maker_stats = TraderStats::default();
+        let mut taker_stats = TraderStats::default();
+
+        // 1.5 token A at 9 decimals, then 1.5 token A at 6 decimals
+        TraderStats::record_fill(
+            Some(&pair_fee_config),
+            1_500_000_000,
+            9,
+            Some(&mut maker_stats),
+            Some(&mut taker_stats),
+        );
+        TraderStats::record_fill(
+            Some(&pair_fee_config),
+            1_500_000,
+            6,
+            Some(&mut maker_stats),
+            Some(&mut taker_stats),
+        );
+
+        assert_eq!(maker_stats.maker_volume, 6_000_000);
+        assert_eq!(maker_stats.taker_volume, 0);
+        assert_eq!(taker_stats.taker_volume, 6_000_000);
+        assert_eq!(taker_stats.maker_volume, 0);
+    }
+
+    #[test]
+    fn fills_record_no_volume_without_a_pair_rate() {
+        let mut trader_stats = TraderStats::default();
+
+        TraderStats::record_fill(None, u64::MAX, 0, Some(&mut trader_stats), None);
+        TraderStats::record_fill(
+            Some(&PairFeeConfig::default()),
+            u64::MAX,
+            0,
+            Some(&mut trader_stats),
+            None,
+        );
+
+        assert_eq!(trader_stats.maker_volume, 0);
+    }
+
+    #[test]
+    fn fill_volume_saturates() {
+        let pair_fee_config = PairFeeConfig {
+            volume_per_token_a: u64::MAX,
+            ..Default::default()
+        };
+        let mut trader_stats = TraderStats {
+            taker_volume: 1,
+            ..Default::default()
+        };
+
+        TraderStats::record_fill(
+            Some(&pair_fee_config),
+            u64::MAX,
+            0,
+            None,
+            Some(&mut trader_stats),
+        );
+
+        assert_eq!(trader_stats.taker_volume, u64::MAX);
+    }
+}
+
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace, Debug, PartialEq, Eq,
+)]
+pub struct FeeSplitRecipient {
+    pub wallet: Pubkey,
+    pub weight_bps: u16,
+}
+
+#[account]
+#[derive(Default, InitSpace, Debug)]
+pub struct FeeSplitConfig {
+    #[max_len(8)]
+    pub recipients: Vec<FeeSplitRecipient>,
+    pub bump: u8,
+}
+
+impl FeeSplitConfig {
+    pub const SEED: &'static [u8] = b"fee_split";
+    pub const MAX_RECIPIENTS: usize = 8;
+
+    pub fn write(
+        fee_split_config: &mut Account<'_, FeeSplitConfig>,
+        recipients: Option<Vec<FeeSplitRecipient>>,
+        bump: Option<u8>,
+    ) -> Result<Self> {
+        if let Some(r) = recipients {
+            fee_split_config.recipients = r;
+        }
+        if let Some(b) = bump {
+            fee_split_config.bump = b;
+        }
+
+        Ok(Self {
+            recipients: fee_split_config.recipients.clone(),
+            bump: fee_split_config.bump,
+        })
+    }
+
+    /// Check that the recipients are unique, within `MAX_RECIPIENTS` and that their weights
+    /// add up to exactly `MAX_BPS_VALUE`.
+    pub fn assert_is_valid_split(recipients: &[FeeSplitRecipient]) -> Result<()> {
+        if recipients.is_empty() || recipients.len() > Self::MAX_RECIPIENTS {
+            return Err(EscrowError::InvalidFeeSplit.into());
+        }
+
+        for (i, recipient) in recipients.iter().enumerate() {
+            if recipients[..i]
+                .iter()
+                .any(|other| other.wallet == recipient.wallet)
+            {
+                return Err(EscrowError::InvalidFeeSplit.into());
+            }
+        }
+
+        let total_weight_bps = recipients
+            .iter()
+            .try_fold(0u16, |total, recipient| {
+                total.checked_add(recipient.weight_bps)
+            })
+            .ok_or(EscrowError::InvalidFeeSplit)?;
+
+        if total_weight_bps != MAX_BPS_VALUE {
+            return Err(EscrowError::InvalidFeeSplit.into());
+        }
+
+        Ok(())
+    }
+
+    /// Split `amount` by the recipients' weights.
+    ///
+    /// Every share is rounded down and the remainder goes to the first recipient, so the
+    /// shares always add up to `amount`.
+    pub fn get_split_amounts(&self, amount: u64) -> Result<Vec<u64>> {
+        let mut split_amounts = self
+            .recipients
+            .iter()
+            .map(|recipient| {
+                u128::from(amount)
+                    .checked_mul(u128::from(recipient.weight_bps))
+                    .and_then(|v| v.checked_div(u128::from(MAX_BPS_VALUE)))
+                    .and_then(|v| u64::try_from(v).ok())
+                    .ok_or(EscrowError::MathError.into())
+            })
+            .collect::<Result<Vec<u64>>>()?;
+
+        let distributed_amount = split_amounts
+            .iter()
+            .try_fold(0u64, |total, split_amount| total.checked_add(*split_amount))
+            .ok_or(EscrowError::MathError)?;
+
+        if let Some(first_split_amount) = split_amounts.first_mut() {
+            *first_split_amount = amount
+                .checked_sub(distributed_amount)
+                .and_then(|remainder| first_split_amount.checked_add(remainder))
+                .ok_or(EscrowError::MathError)?;
+        }
+
+        Ok(split_amounts)
+    }
+}
+
+#[cfg(test)]
+mod fee_split_config_tests {
+    use super::*;
+
+    fn fee_split_config(weights_bps: &[u16]) -> FeeSplitConfig {
+        FeeSplitConfig {
+            recipients: weights_bps
+                .iter()
+                .map(|weight_bps| FeeSplitRecipient {
+                    wallet: Pubkey::new_unique(),
+                    weight_bps: *weight_bps,
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn fee_split_requires_weights_to_sum_to_max_bps() {
+        let valid = fee_split_config(&[5_000, 3_000, 2_000]);
+        let short = fee_split_config(&[5_000, 3_000]);
+        let over = fee_split_config(&[MAX_BPS_VALUE, 1]);
+
+        assert!(FeeSplitConfig::assert_is_valid_split(&valid.recipients).is_ok());
+        assert!(FeeSplitConfig::assert_is_valid_split(&short.recipients).is_err());
+        assert!(FeeSplitConfig::assert_is_valid_split(&over.recipients).is_err());
+        assert!(FeeSplitConfig::assert_is_valid_split(&[]).is_err());
+    }
+
+    #[test]
+    fn fee_split_rejects_duplicate_and_excess_recipients() {
+        let mut duplicated = fee_split_config(&[5_000, 5_000]);
+        duplicated.recipients[1].wallet = duplicated.recipients[0].wallet;
+        let excess = fee_split_config(&[1_000; FeeSplitConfig::MAX_RECIPIENTS + 1]);
+
+        assert!(FeeSplitConfig::assert_is_valid_split(&duplicated.recipients).is_err());
+        assert!(FeeSplitConfig::assert_is_valid_split(&excess.recipients).is_err());
+    }
+
+    #[test]
+    fn fee_split_amounts_add_up_to_the_amount() {
+        let config = fee_split_config(&[5_000, 3_000, 2_000]);
+
+        assert_eq!(
+            config.get_split_amounts(1_000).unwrap(),
+            vec![500, 300, 200]
+        );
+        assert_eq!(config.get_split_amounts(7).unwrap(), vec![4, 2, 1]);
+        assert_eq!(
+            config
+                .get_split_amounts(u64::MAX)
+                .unwrap()
+                .iter()
+                .sum::<u64>(),
+            u64::MAX
+        );
+    }
+}
+
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace, Debug, PartialEq, Eq,
+)]
+pub struct BasketLeg {
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+#[account]
+#[derive(Default, InitSpace, Debug)]
+pub struct BasketOffer {
+    pub id: u64,
+    pub maker: Pubkey,
+    /// Legs escrowed by the maker, each in a vault owned by the basket offer
+    #[max_len(4)]
+    pub offered: Vec<BasketLeg>,
+    /// Legs the taker pays to the maker
+    #[max_len(4)]
+    pub wanted: Vec<BasketLeg>,
+    pub funder: Pubkey,
+    pub created_at: i64,
+    pub expires_at: Option<i64>,
+    pub allowed_taker: Option<Pubkey>,
+    pub bump: u8,
+}
+
+impl BasketOffer {
+    pub const SEED_PREFIX: &'static [u8] = b"basket_offer";
+    pub const MAX_LEGS: usize = 4;
+
+    pub fn write(
+        basket_offer: &mut Account<'_, BasketOffer>,
+        id: Option<u64>,
+        maker: Option<Pubkey>,
+        offered: Option<Vec<BasketLeg>>,
+        wanted: Option<Vec<BasketLeg>>,
+        bump: Option<u8>,
+    ) -> Result<Self> {
+        if let Some(i) = id {
+            basket_offer.id = i;
+        }
+        if let Some(m) = maker {
+            basket_offer.maker = m;
+        }
+        if let Some(o) = offered {
+            basket_offer.offered = o;
+        }
+        if let Some(w) = wanted {
+            basket_offer.wanted = w;
+        }
+        if let Some(b) = bump {
+            basket_offer.bump = b;
+        }
+
+        Ok(Self {
+            id: basket_offer.id,
+            maker: basket_offer.maker,
+            offered: basket_offer.offered.clone(),
+            wanted: basket_offer.wanted.clone(),
+            funder: basket_offer.funder,
+            created_at: basket_offer.created_at,
+            expires_at: basket_offer.expires_at,
+            allowed_taker: basket_offer.allowed_taker,
+            bump: basket_offer.bump,
+        })
+    }
+
+    /// Check that one side of a basket has between one and `MAX_LEGS` legs of distinct
+    /// mints, none of them for a zero amount.
+    pub fn assert_is_valid_legs(legs: &[BasketLeg]) -> Result<()> {
+        if legs.is_empty() || legs.len() > Self::MAX_LEGS {
+            return Err(EscrowError::InvalidBasket.into());
+        }
+
+        for (i, leg) in legs.iter().enumerate() {
+            if leg.amount == 0 || legs[..i].iter().any(|other| other.mint == leg.mint) {
+                return Err(EscrowError::InvalidBasket.into());
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        matches!(self.expires_at, Some(expires_at) if now >= expires_at)
+    }
+
+    /// Private basket offers can only be taken by their designated taker.
+    pub fn is_taker_allowed(&self, taker: &Pubkey) -> bool {
+        match self.allowed_taker {
+            Some(allowed_taker) => allowed_taker == *taker,
+            None => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod basket_offer_tests {
+    use super::*;
+
+    fn basket_leg(amount: u64) -> BasketLeg {
+        BasketLeg {
+            mint: Pubkey::new_unique(),
+            amount,
+        }
+    }
+
+    #[test]
+    fn basket_legs_must_be_distinct_and_non_zero() {
+        let leg = basket_leg(100);
+
+        assert!(BasketOffer::assert_is_valid_legs(&[leg, basket_leg(1)]).is_ok());
+        assert!(BasketOffer::assert_is_valid_legs(&[]).is_err());
+        assert!(BasketOffer::assert_is_valid_legs(&[leg, basket_leg(0)]).is_err());
+        assert!(BasketOffer::assert_is_valid_legs(&[leg, leg]).is_err());
+    }
+
+    #[test]
+    fn basket_legs_are_limited_to_the_maximum_count() {
+        let legs = (0..=BasketOffer::MAX_LEGS)
+            .map(|_| basket_leg(1))
+            .collect::<Vec<_>>();
+
+        assert!(BasketOffer::assert_is_valid_legs(&legs[..BasketOffer::MAX_LEGS]).is_ok());
+        assert!(BasketOffer::assert_is_valid_legs(&legs).is_err());
+    }
 }
@@ -1,6 +1,55 @@
-use crate::{consts::MAX_BPS_VALUE, errors::EscrowError};
+use crate::{consts::MAX_BPS_VALUE, errors::EscrowError, state::MintConfig};
 use anchor_lang::prelude::*;
-use anchor_spl::token::{close_account, transfer, CloseAccount, Transfer};
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::system_program::{transfer, Transfer};
+use anchor_spl::{
+    associated_token::{
+        create_idempotent, get_associated_token_address_with_program_id, AssociatedToken, Create,
+    },
+    token::spl_token,
+    token_2022::{
+        spl_token_2022::{
+            self,
+            extension::{
+                transfer_fee::TransferFeeConfig, BaseStateWithExtensions, StateWithExtensions,
+            },
+            state::Mint as MintState,
+        },
+        Token2022,
+    },
+    token_interface::{
+        close_account, harvest_withheld_tokens_to_mint, sync_native, transfer_checked,
+        CloseAccount, HarvestWithheldTokensToMint, Mint, SyncNative, TokenAccount, TransferChecked,
+    },
+};
+
+/// Check the balance of an account which was sent `amount` of `mint` on top of
+/// `amount_before`, and return the amount it actually received.
+///
+/// Mints with the Token-2022 TransferFee extension withhold part of every transfer, so
+/// the recipient receives `amount` **net of the transfer fee**. Offers, bids and fills
+/// record and settle on these net amounts, never on the amount sent.
+pub fn assert_received_net(
+    label: &str,
+    mint: &InterfaceAccount<'_, Mint>,
+    actual: u64,
+    amount_before: u64,
+    amount: u64,
+) -> Result<u64> {
+    let received_amount = amount
+        .checked_sub(get_transfer_fee(mint, amount)?)
+        .ok_or(EscrowError::MathError)?;
+
+    assert_balance_invariant(
+        label,
+        actual,
+        amount_before
+            .checked_add(received_amount)
+            .ok_or(EscrowError::MathError)?,
+    )?;
+
+    Ok(received_amount)
+}
 
 pub fn assert_is_bps_in_range(bps: u16) -> Result<()> {
     if bps > MAX_BPS_VALUE {
@@ -10,9 +59,178 @@ pub fn assert_is_bps_in_range(bps: u16) -> Result<()> {
     Ok(())
 }
 
+/// Check a balance read back after a transfer against the expected balance.
+///
+/// A mismatch is logged with `label` and fails with a typed error rather than a panic.
+pub fn assert_balance_invariant(label: &str, actual: u64, expected: u64) -> Result<()> {
+    if actual != expected {
+        msg!(
+            "Balance invariant violated : {} is {}, expected {}",
+            label,
+            actual,
+            expected
+        );
+        return err!(EscrowError::BalanceInvariantViolated);
+    }
+
+    Ok(())
+}
+
+/// Deserialize a program-owned PDA which may not have been created yet.
+///
+/// Callers must check the PDA seeds, so an absent account cannot be faked by
+/// passing some other account.
+pub fn load_optional_account<T: AccountDeserialize>(
+    account: &AccountInfo<'_>,
+) -> Result<Option<T>> {
+    if *account.owner != crate::ID || account.data_is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(T::try_deserialize(
+        &mut &account.try_borrow_data()?[..],
+    )?))
+}
+
+/// Write back a program-owned PDA read with `load_optional_account`, unless it was absent.
+pub fn store_optional_account<T: AccountSerialize>(
+    account: &AccountInfo<'_>,
+    value: Option<&T>,
+) -> Result<()> {
+    if let Some(value) = value {
+        value.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])?;
+    }
+
+    Ok(())
+}
+
+/// Deserialize a mint passed as a remaining account, checking that it is `mint` and
+/// owned by `token_program`.
+pub fn load_mint<'info>(
+    account: &'info AccountInfo<'info>,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Result<InterfaceAccount<'info, Mint>> {
+    if account.key != mint || account.owner != token_program {
+        return err!(EscrowError::BasketAccountMismatch);
+    }
+
+    InterfaceAccount::try_from(account)
+}
+
+/// Load the mint config of `mint` passed as a remaining account, which is empty unless
+/// the manager created it.
+pub fn load_mint_config(account: &AccountInfo<'_>, mint: &Pubkey) -> Result<Option<MintConfig>> {
+    let (mint_config, _) =
+        Pubkey::find_program_address(&[MintConfig::SEED_PREFIX, mint.as_ref()], &crate::ID);
+
+    if *account.key != mint_config {
+        return err!(EscrowError::BasketAccountMismatch);
+    }
+
+    load_optional_account::<MintConfig>(account)
+}
+
+/// Check that a token account passed as a remaining account is the ATA of `wallet`.
+pub fn assert_associated_token_account(
+    token_account: &AccountInfo<'_>,
+    wallet: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Result<()> {
+    if *token_account.key
+        != get_associated_token_address_with_program_id(wallet, mint, token_program)
+    {
+        return err!(EscrowError::TokenAccountMismatch);
+    }
+
+    Ok(())
+}
+
+/// Create the ATA of `wallet` for `mint` unless it already exists.
+pub fn create_associated_token_account<'info>(
+    associated_token_program: &Program<'info, AssociatedToken>,
+    payer: AccountInfo<'info>,
+    associated_token: AccountInfo<'info>,
+    wallet: AccountInfo<'info>,
+    mint: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+) -> Result<()> {
+    create_idempotent(CpiContext::new(
+        associated_token_program.to_account_info(),
+        Create {
+            payer,
+            associated_token,
+            authority: wallet,
+            mint,
+            system_program,
+            token_program,
+        },
+    ))
+}
+
+/// Read the balance of a token account passed as a remaining account.
+pub fn get_token_account_amount(token_account: &AccountInfo<'_>) -> Result<u64> {
+    let token_account = TokenAccount::try_deserialize(&mut &token_account.try_borrow_data()?[..])?;
+
+    Ok(token_account.amount)
+}
+
+pub fn is_native_mint(mint: &Pubkey) -> bool {
+    *mint == spl_token::native_mint::ID || *mint == spl_token_2022::native_mint::ID
+}
+
+/// Wrap lamports into a native mint token account.
+///
+/// The lamports are moved with a system transfer, after which `sync_native` credits
+/// them to the token account balance.
+pub fn wrap_sol<'info>(
+    system_program: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    from: AccountInfo<'info>,
+    to_token_account: AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    transfer(
+        CpiContext::new(
+            system_program,
+            Transfer {
+                from,
+                to: to_token_account.clone(),
+            },
+        ),
+        amount,
+    )?;
+
+    sync_native(CpiContext::new(
+        token_program,
+        SyncNative {
+            account: to_token_account,
+        },
+    ))
+}
+
+/// Verify that `leaf` is part of the merkle tree with the given `root`.
+///
+/// Sibling pairs are hashed in sorted order, so proofs do not need to carry
+/// left/right position flags.
+pub fn verify_merkle_proof(proof: &[[u8; 32]], root: [u8; 32], leaf: [u8; 32]) -> bool {
+    let computed_root = proof.iter().fold(leaf, |node, sibling| {
+        if node <= *sibling {
+            hashv(&[&node, sibling]).to_bytes()
+        } else {
+            hashv(&[sibling, &node]).to_bytes()
+        }
+    });
+
+    computed_root == root
+}
+
 pub fn transfer_token<'info>(
     token_program: AccountInfo<'info>,
     from_token_account: AccountInfo<'info>,
+    mint: &InterfaceAccount<'info, Mint>,
     to_token_account: AccountInfo<'info>,
     authority: AccountInfo<'info>,
     amount: u64,
@@ -21,8 +239,9 @@ pub fn transfer_token<'info>(
     // 기본 CPI 컨텍스트 구성
     let cpi_ctx = CpiContext::new(
         token_program,
-        Transfer {
+        TransferChecked {
             from: from_token_account,
+            mint: mint.to_account_info(),
             to: to_token_account,
             authority,
         },
@@ -31,10 +250,10 @@ pub fn transfer_token<'info>(
     match signer_seeds {
         Some(seeds) => {
             let seeds = [seeds];
-            transfer(cpi_ctx.with_signer(&seeds), amount)?;
+            transfer_checked(cpi_ctx.with_signer(&seeds), amount, mint.decimals)?;
         }
         None => {
-            transfer(cpi_ctx, amount)?;
+            transfer_checked(cpi_ctx, amount, mint.decimals)?;
         }
     }
 
@@ -69,3 +288,77 @@ pub fn close_token_account<'info>(
 
     Ok(())
 }
+
+/// Read the Token-2022 TransferFee extension of a mint, if it has one.
+pub fn get_transfer_fee_config(
+    mint: &InterfaceAccount<'_, Mint>,
+) -> Result<Option<TransferFeeConfig>> {
+    let mint_info = mint.to_account_info();
+
+    if *mint_info.owner != Token2022::id() {
+        return Ok(None);
+    }
+
+    let mint_data = mint_info.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<MintState>::unpack(&mint_data)?;
+
+    Ok(mint_state
+        .get_extension::<TransferFeeConfig>()
+        .ok()
+        .copied())
+}
+
+/// Calculate the transfer fee withheld by the token program when transferring `amount`.
+///
+/// The recipient of a transfer **receives `amount` minus this fee**. Mints without the
+/// TransferFee extension, including every SPL Token mint, always return zero.
+pub fn get_transfer_fee(mint: &InterfaceAccount<'_, Mint>, amount: u64) -> Result<u64> {
+    match get_transfer_fee_config(mint)? {
+        Some(transfer_fee_config) => transfer_fee_config
+            .calculate_epoch_fee(Clock::get()?.epoch, amount)
+            .ok_or(EscrowError::MathError.into()),
+        None => Ok(0),
+    }
+}
+
+/// Unwrap native SOL received into a wSOL token account by closing it back to its owner.
+///
+/// The account is only closed if it held no wSOL before the transfer, so wSOL the owner
+/// already held is never unwrapped along with it. Otherwise the SOL stays wrapped.
+pub fn unwrap_sol<'info>(
+    token_program: AccountInfo<'info>,
+    token_account: AccountInfo<'info>,
+    owner: AccountInfo<'info>,
+    amount_before_transfer: u64,
+) -> Result<()> {
+    if amount_before_transfer != 0 {
+        return Ok(());
+    }
+
+    close_token_account(token_program, token_account, owner.clone(), owner, None)
+}
+
+/// Harvest transfer fees withheld in a token account to its mint.
+///
+/// Token-2022 refuses to close an account which still holds withheld fees, so this
+/// has to run before `close_token_account` for mints with the TransferFee extension.
+pub fn harvest_withheld_fees<'info>(
+    token_program: AccountInfo<'info>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_account: AccountInfo<'info>,
+) -> Result<()> {
+    if get_transfer_fee_config(mint)?.is_none() {
+        return Ok(());
+    }
+
+    harvest_withheld_tokens_to_mint(
+        CpiContext::new(
+            token_program.clone(),
+            HarvestWithheldTokensToMint {
+                token_program_id: token_program,
+                mint: mint.to_account_info(),
+            },
+        ),
+        vec![token_account],
+    )
+}
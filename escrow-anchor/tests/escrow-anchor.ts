@@ -1,6 +1,8 @@
 import * as anchor from "@coral-xyz/anchor";
 import * as token from "@solana/spl-token";
+import { createHash } from "crypto";
 import { Program } from "@coral-xyz/anchor";
+import { expect } from "chai";
 import { EscrowAnchor } from "../target/types/escrow_anchor";
 
 describe("escrow-anchor", () => {
@@ -36,6 +38,35 @@ describe("escrow-anchor", () => {
   const tokenMintKeypairB = anchor.web3.Keypair.generate();
   const tokenMintB = tokenMintKeypairB.publicKey;
 
+  const tokenMint2022Keypair = anchor.web3.Keypair.generate();
+  const tokenMint2022 = tokenMint2022Keypair.publicKey;
+
+  const mintConfigAddress = (mint: anchor.web3.PublicKey) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("mint_config"), mint.toBuffer()],
+      program.programId
+    )[0];
+
+  const pairFeeConfigAddress = (
+    mintA: anchor.web3.PublicKey,
+    mintB: anchor.web3.PublicKey
+  ) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("pair_fee"), mintA.toBuffer(), mintB.toBuffer()],
+      program.programId
+    )[0];
+
+  const traderStatsAddress = (trader: anchor.web3.PublicKey) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("trader_stats"), trader.toBuffer()],
+      program.programId
+    )[0];
+
+  const feeSplitConfig = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("fee_split")],
+    program.programId
+  )[0];
+
   const escrowTokenAFeeAccount = anchor.utils.token.associatedAddress({
     mint: tokenMintA,
     owner: escrowState
@@ -52,6 +83,27 @@ describe("escrow-anchor", () => {
   const takerKeypair = anchor.web3.Keypair.generate();
   const taker = takerKeypair.publicKey;
 
+  // A taker which is neither the allowed taker nor on any allowlist
+  const outsiderKeypair = anchor.web3.Keypair.generate();
+  const outsider = outsiderKeypair.publicKey;
+
+  const referrer = anchor.web3.Keypair.generate().publicKey;
+
+  const referrerTokenAAccount = anchor.utils.token.associatedAddress({
+    mint: tokenMintA,
+    owner: referrer
+  });
+
+  const referrerTokenBAccount = anchor.utils.token.associatedAddress({
+    mint: tokenMintB,
+    owner: referrer
+  });
+
+  const referrerRegistration = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("referrer"), referrer.toBuffer()],
+    program.programId
+  )[0];
+
   const makerTokenAAccount = anchor.utils.token.associatedAddress({
     mint: tokenMintA,
     owner: maker
@@ -90,6 +142,42 @@ describe("escrow-anchor", () => {
     program.programId
   )[0];
 
+  const escrowAccount3 = anchor.web3.PublicKey.findProgramAddressSync(
+    [
+      Buffer.from("offer"),
+      maker.toBuffer(),
+      new anchor.BN(2).toArrayLike(Buffer, "le", 8)
+    ],
+    program.programId
+  )[0];
+
+  const escrowAccount4 = anchor.web3.PublicKey.findProgramAddressSync(
+    [
+      Buffer.from("offer"),
+      maker.toBuffer(),
+      new anchor.BN(3).toArrayLike(Buffer, "le", 8)
+    ],
+    program.programId
+  )[0];
+
+  const escrowAccount5 = anchor.web3.PublicKey.findProgramAddressSync(
+    [
+      Buffer.from("offer"),
+      maker.toBuffer(),
+      new anchor.BN(4).toArrayLike(Buffer, "le", 8)
+    ],
+    program.programId
+  )[0];
+
+  const escrowAccount6 = anchor.web3.PublicKey.findProgramAddressSync(
+    [
+      Buffer.from("offer"),
+      maker.toBuffer(),
+      new anchor.BN(5).toArrayLike(Buffer, "le", 8)
+    ],
+    program.programId
+  )[0];
+
   const escrowTokenAVaultAccount1 = anchor.utils.token.associatedAddress({
     mint: tokenMintA,
     owner: escrowAccount1
@@ -100,20 +188,136 @@ describe("escrow-anchor", () => {
     owner: escrowAccount2
   });
 
+  const escrowTokenAVaultAccount3 = anchor.utils.token.associatedAddress({
+    mint: tokenMintA,
+    owner: escrowAccount3
+  });
+
+  const escrowTokenAVaultAccount6 = anchor.utils.token.associatedAddress({
+    mint: tokenMintA,
+    owner: escrowAccount6
+  });
+
+  const makerToken2022Account = token.getAssociatedTokenAddressSync(
+    tokenMint2022,
+    maker,
+    false,
+    token.TOKEN_2022_PROGRAM_ID
+  );
+
+  const takerToken2022Account = token.getAssociatedTokenAddressSync(
+    tokenMint2022,
+    taker,
+    false,
+    token.TOKEN_2022_PROGRAM_ID
+  );
+
+  const escrowToken2022FeeAccount = token.getAssociatedTokenAddressSync(
+    tokenMint2022,
+    escrowState,
+    true,
+    token.TOKEN_2022_PROGRAM_ID
+  );
+
+  const escrowToken2022VaultAccount4 = token.getAssociatedTokenAddressSync(
+    tokenMint2022,
+    escrowAccount4,
+    true,
+    token.TOKEN_2022_PROGRAM_ID
+  );
+
+  const escrowNativeVaultAccount5 = anchor.utils.token.associatedAddress({
+    mint: token.NATIVE_MINT,
+    owner: escrowAccount5
+  });
+
+  const takerNativeAccount = anchor.utils.token.associatedAddress({
+    mint: token.NATIVE_MINT,
+    owner: taker
+  });
+
+  const escrowNativeFeeAccount = anchor.utils.token.associatedAddress({
+    mint: token.NATIVE_MINT,
+    owner: escrowState
+  });
+
+  const expectAnchorError = async (
+    transaction: Promise<string>,
+    errorCode: string
+  ) => {
+    try {
+      await transaction;
+    } catch (err) {
+      expect(err).to.be.instanceOf(anchor.AnchorError);
+      expect(err.error.errorCode.code).to.equal(errorCode);
+      return;
+    }
+    expect.fail(`expected the transaction to fail with ${errorCode}`);
+  };
+
+  const takeOffer1Accounts = (taker: anchor.web3.PublicKey) => ({
+    escrowState: escrowState,
+    escrowAccount: escrowAccount1,
+    tokenAMintAccount: tokenMintA,
+    tokenBMintAccount: tokenMintB,
+    tokenAMintConfig: mintConfigAddress(tokenMintA),
+    tokenBMintConfig: mintConfigAddress(tokenMintB),
+    pairFeeConfig: pairFeeConfigAddress(tokenMintA, tokenMintB),
+    makerTraderStats: traderStatsAddress(maker),
+    takerTraderStats: traderStatsAddress(taker),
+    makerTokenBAccount: makerTokenBAccount,
+    takerTokenAAccount: anchor.utils.token.associatedAddress({
+      mint: tokenMintA,
+      owner: taker
+    }),
+    takerTokenBAccount: anchor.utils.token.associatedAddress({
+      mint: tokenMintB,
+      owner: taker
+    }),
+    escrowTokenAFeeAccount: escrowTokenAFeeAccount,
+    escrowTokenBFeeAccount: escrowTokenBFeeAccount,
+    escrowTokenAVaultAccount: escrowTokenAVaultAccount1,
+    referrer: null,
+    referrerRegistration: null,
+    referrerTokenAAccount: null,
+    referrerTokenBAccount: null,
+    maker: maker,
+    funder: fundingAccount,
+    taker: taker,
+    fundingAccount: fundingAccount,
+    tokenAProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+    tokenBProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+    associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+    systemProgram: anchor.web3.SystemProgram.programId
+  });
+
   const initializeArgs = { makerFeeBps: 100, takerFeeBps: 100 };
 
   const setFeesArgs = { makerFeeBps: 200, takerFeeBps: 200 };
 
+  // A single-leaf allowlist: the merkle root is the leaf itself and the proof is empty
+  const takerAllowlistRoot = Array.from(
+    createHash("sha256").update(taker.toBuffer()).digest()
+  );
+
   const makeOffer1Args = {
     id: new anchor.BN(0),
     tokenAOfferedAmount: new anchor.BN(1000000000000),
-    tokenBWantedAmount: new anchor.BN(1000000000000)
+    tokenBWantedAmount: new anchor.BN(1000000000000),
+    expiresAt: null,
+    allowedTaker: null,
+    takerMerkleRoot: takerAllowlistRoot,
+    kind: { fixed: {} }
   };
 
   const makeOffer2Args = {
     id: new anchor.BN(1),
     tokenAOfferedAmount: new anchor.BN(1000000000000),
-    tokenBWantedAmount: new anchor.BN(1000000000000)
+    tokenBWantedAmount: new anchor.BN(1000000000000),
+    expiresAt: null,
+    allowedTaker: null,
+    takerMerkleRoot: null,
+    kind: { fixed: {} }
   };
 
   before(async () => {
@@ -152,12 +356,31 @@ describe("escrow-anchor", () => {
       tokenMintKeypairB
     );
 
+    await token.createMint(
+      program.provider.connection,
+      fundingAccountKeypair,
+      fundingAccount,
+      fundingAccount,
+      6,
+      tokenMint2022Keypair,
+      undefined,
+      token.TOKEN_2022_PROGRAM_ID
+    );
+
     await token.createAccount(
       program.provider.connection,
       fundingAccountKeypair,
       tokenMintA,
       maker
     );
+    await token.createAssociatedTokenAccount(
+      program.provider.connection,
+      fundingAccountKeypair,
+      tokenMint2022,
+      maker,
+      undefined,
+      token.TOKEN_2022_PROGRAM_ID
+    );
     await token.createAccount(
       program.provider.connection,
       fundingAccountKeypair,
@@ -174,13 +397,25 @@ describe("escrow-anchor", () => {
       2000000000000
     );
 
+    await token.mintTo(
+      program.provider.connection,
+      fundingAccountKeypair,
+      tokenMint2022,
+      makerToken2022Account,
+      fundingAccountKeypair,
+      1000000000,
+      [],
+      undefined,
+      token.TOKEN_2022_PROGRAM_ID
+    );
+
     await token.mintTo(
       program.provider.connection,
       fundingAccountKeypair,
       tokenMintB,
       takerTokenBAccount,
       fundingAccountKeypair,
-      1000000000000
+      2000000000000
     );
   });
 
@@ -226,145 +461,2069 @@ describe("escrow-anchor", () => {
     console.log("Your transaction signature", tx);
   });
 
-  it("make offer", async () => {
+  it("escrow state cancel manager transfer", async () => {
     const tx = await program.methods
-      .makeOffer(makeOffer1Args)
+      .cancelManagerTransfer()
       .accounts({
-        escrowAccount: escrowAccount1,
-        tokenAMintAccount: tokenMintA,
-        tokenBMintAccount: tokenMintB,
-        makerTokenAAccount: makerTokenAAccount,
-        escrowTokenAVaultAccount: escrowTokenAVaultAccount1,
-        maker: maker,
-        fundingAccount: fundingAccount,
-        tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
-        associatedProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
-        systemProgram: anchor.web3.SystemProgram.programId
+        escrowState: escrowState,
+        escrowManager: escrowManager
       })
-      .signers([makerKeypair, fundingAccountKeypair])
+      .signers([escrowManagerKeypair])
       .rpc();
     console.log("Your transaction signature", tx);
   });
 
-  it("take offer", async () => {
+  it("escrow state set manager", async () => {
+    // Add your test here.
     const tx = await program.methods
-      .takeOffer()
-      .preInstructions([
-        anchor.web3.ComputeBudgetProgram.setComputeUnitLimit({ units: 400_000 })
-      ])
+      .setManager()
       .accounts({
         escrowState: escrowState,
-        escrowAccount: escrowAccount1,
-        tokenAMintAccount: tokenMintA,
-        tokenBMintAccount: tokenMintB,
-        makerTokenBAccount: makerTokenBAccount,
-        takerTokenAAccount: takerTokenAAccount,
-        takerTokenBAccount: takerTokenBAccount,
-        escrowTokenAFeeAccount: escrowTokenAFeeAccount,
-        escrowTokenBFeeAccount: escrowTokenBFeeAccount,
-        escrowTokenAVaultAccount: escrowTokenAVaultAccount1,
-        maker: maker,
-        taker: taker,
-        fundingAccount: fundingAccount,
-        tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
-        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
-        systemProgram: anchor.web3.SystemProgram.programId
+        escrowManager: escrowManager,
+        newManager: newManager
       })
-      .signers([takerKeypair, fundingAccountKeypair])
-      .rpc({ skipPreflight: true });
+      .signers([escrowManagerKeypair])
+      .rpc();
     console.log("Your transaction signature", tx);
   });
 
-  it("make offer", async () => {
+  it("escrow state accept manager", async () => {
     const tx = await program.methods
-      .makeOffer(makeOffer2Args)
+      .acceptManager()
       .accounts({
-        escrowAccount: escrowAccount2,
-        tokenAMintAccount: tokenMintA,
-        tokenBMintAccount: tokenMintB,
-        makerTokenAAccount: makerTokenAAccount,
-        escrowTokenAVaultAccount: escrowTokenAVaultAccount2,
-        maker: maker,
-        fundingAccount: fundingAccount,
-        tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
-        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
-        systemProgram: anchor.web3.SystemProgram.programId
+        escrowState: escrowState,
+        newManager: newManager
       })
-      .signers([makerKeypair, fundingAccountKeypair])
+      .signers([newManagerKeypair])
       .rpc();
     console.log("Your transaction signature", tx);
   });
 
-  it("cancel offer", async () => {
+  it("escrow state pause and resume", async () => {
+    await program.methods
+      .setPause({ makePaused: true, takePaused: true })
+      .accounts({
+        escrowState: escrowState,
+        escrowManager: newManager
+      })
+      .signers([newManagerKeypair])
+      .rpc();
+
     const tx = await program.methods
-      .cancelOffer()
+      .setPause({ makePaused: false, takePaused: false })
       .accounts({
-        escrowAccount: escrowAccount2,
-        tokenAMintAccount: tokenMintA,
-        makerTokenAAccount: makerTokenAAccount,
-        escrowTokenAVaultAccount: escrowTokenAVaultAccount2,
-        maker: maker,
-        fundingAccount: fundingAccount,
-        tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
-        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
-        systemProgram: anchor.web3.SystemProgram.programId
+        escrowState: escrowState,
+        escrowManager: newManager
       })
-      .signers([makerKeypair, fundingAccountKeypair])
+      .signers([newManagerKeypair])
       .rpc();
     console.log("Your transaction signature", tx);
   });
 
-  it("escrow state collect fee A", async () => {
-    const escrowFeeAccount = anchor.utils.token.associatedAddress({
-      mint: tokenMintA,
-      owner: escrowState
-    });
-    const managerTokenAccount = anchor.utils.token.associatedAddress({
-      mint: tokenMintA,
-      owner: newManager
-    });
+  it("escrow state set fee rounding mode", async () => {
+    const tx = await program.methods
+      .setFeeRoundingMode({ feeRoundingMode: { ceil: {} } })
+      .accounts({
+        escrowState: escrowState,
+        escrowManager: newManager
+      })
+      .signers([newManagerKeypair])
+      .rpc();
+    console.log("Your transaction signature", tx);
+  });
 
-    // Add your test here.
+  it("escrow state set referral share", async () => {
     const tx = await program.methods
-      .collectFee({ shouldCloseFeeAccount: false })
+      .setReferralShare({ referralShareBps: 2_000 })
+      .accounts({
+        escrowState: escrowState,
+        escrowManager: newManager
+      })
+      .signers([newManagerKeypair])
+      .rpc();
+    console.log("Your transaction signature", tx);
+  });
+
+  it("escrow state register referrer", async () => {
+    const tx = await program.methods
+      .registerReferrer()
+      .accounts({
+        escrowState: escrowState,
+        escrowManager: newManager,
+        referrerWallet: referrer,
+        referrer: referrerRegistration,
+        systemProgram: anchor.web3.SystemProgram.programId
+      })
+      .signers([newManagerKeypair])
+      .rpc();
+    console.log("Your transaction signature", tx);
+  });
+
+  it("escrow state set fee tiers", async () => {
+    const tx = await program.methods
+      .setFeeTiers({
+        feeTiers: [
+          { volumeThreshold: new anchor.BN(500000000000), feeDiscountBps: 1_000 },
+          { volumeThreshold: new anchor.BN(5000000000000), feeDiscountBps: 2_500 }
+        ]
+      })
+      .accounts({
+        escrowState: escrowState,
+        escrowManager: newManager
+      })
+      .signers([newManagerKeypair])
+      .rpc();
+    console.log("Your transaction signature", tx);
+  });
+
+  it("migrate escrow state rejects the current layout", async () => {
+    await expectAnchorError(
+      program.methods
+        .migrateEscrowState()
+        .accounts({
+          escrowState: escrowState,
+          escrowManager: newManager,
+          fundingAccount: fundingAccount,
+          systemProgram: anchor.web3.SystemProgram.programId
+        })
+        .signers([newManagerKeypair, fundingAccountKeypair])
+        .rpc(),
+      "EscrowStateAlreadyMigrated"
+    );
+  });
+
+  it("escrow state set mint config and mint list mode", async () => {
+    await program.methods
+      .setMintConfig({
+        allowed: true,
+        denied: false,
+        minFeeAmount: new anchor.BN(1_000),
+        notes: "test token A"
+      })
       .accounts({
         escrowState: escrowState,
         escrowManager: newManager,
         tokenMintAccount: tokenMintA,
-        escrowFeeAccount: escrowFeeAccount,
-        managerTokenAccount: managerTokenAccount,
-        tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
-        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        mintConfig: mintConfigAddress(tokenMintA),
         systemProgram: anchor.web3.SystemProgram.programId
       })
       .signers([newManagerKeypair])
-      .rpc({ skipPreflight: true });
+      .rpc();
+
+    const tx = await program.methods
+      .setMintListMode({ mintListMode: { denylist: {} } })
+      .accounts({
+        escrowState: escrowState,
+        escrowManager: newManager
+      })
+      .signers([newManagerKeypair])
+      .rpc();
     console.log("Your transaction signature", tx);
   });
 
-  it("escrow state collect fee B", async () => {
-    const escrowFeeAccount = anchor.utils.token.associatedAddress({
-      mint: tokenMintB,
-      owner: escrowState
-    });
-    const managerTokenAccount = anchor.utils.token.associatedAddress({
-      mint: tokenMintB,
-      owner: newManager
-    });
+  it("create pair fee config", async () => {
     const tx = await program.methods
-      .collectFee({ shouldCloseFeeAccount: true })
+      .createPairFeeConfig({
+        makerFeeBps: 50,
+        takerFeeBps: 50,
+        volumePerTokenA: new anchor.BN(1_000_000)
+      })
       .accounts({
         escrowState: escrowState,
         escrowManager: newManager,
-        tokenMintAccount: tokenMintB,
-        escrowFeeAccount: escrowFeeAccount,
-        managerTokenAccount: managerTokenAccount,
-        tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
-        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        tokenAMintAccount: tokenMintA,
+        tokenBMintAccount: tokenMintB,
+        pairFeeConfig: pairFeeConfigAddress(tokenMintA, tokenMintB),
         systemProgram: anchor.web3.SystemProgram.programId
       })
       .signers([newManagerKeypair])
-      .rpc({ skipPreflight: true });
+      .rpc();
+    console.log("Your transaction signature", tx);
+  });
+
+  it("update pair fee config", async () => {
+    const tx = await program.methods
+      .updatePairFeeConfig({
+        makerFeeBps: 30,
+        takerFeeBps: 30,
+        volumePerTokenA: new anchor.BN(1_000_000)
+      })
+      .accounts({
+        escrowState: escrowState,
+        escrowManager: newManager,
+        pairFeeConfig: pairFeeConfigAddress(tokenMintA, tokenMintB)
+      })
+      .signers([newManagerKeypair])
+      .rpc();
+    console.log("Your transaction signature", tx);
+  });
+
+  it("create trader stats", async () => {
+    for (const [trader, traderKeypair] of [
+      [maker, makerKeypair],
+      [taker, takerKeypair]
+    ] as const) {
+      const tx = await program.methods
+        .createTraderStats()
+        .accounts({
+          traderStats: traderStatsAddress(trader),
+          trader: trader,
+          fundingAccount: fundingAccount,
+          systemProgram: anchor.web3.SystemProgram.programId
+        })
+        .signers([traderKeypair, fundingAccountKeypair])
+        .rpc();
+      console.log("Your transaction signature", tx);
+    }
+  });
+
+  it("make offer", async () => {
+    const tx = await program.methods
+      .makeOffer(makeOffer1Args)
+      .accounts({
+        escrowState: escrowState,
+        escrowAccount: escrowAccount1,
+        tokenAMintAccount: tokenMintA,
+        tokenBMintAccount: tokenMintB,
+        tokenAMintConfig: mintConfigAddress(tokenMintA),
+        tokenBMintConfig: mintConfigAddress(tokenMintB),
+        makerTokenAAccount: makerTokenAAccount,
+        escrowTokenAVaultAccount: escrowTokenAVaultAccount1,
+        maker: maker,
+        fundingAccount: fundingAccount,
+        tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        associatedProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId
+      })
+      .signers([makerKeypair, fundingAccountKeypair])
+      .rpc();
+    console.log("Your transaction signature", tx);
+  });
+
+  it("take offer rejects a max token B amount below the price", async () => {
+    await expectAnchorError(
+      program.methods
+        .takeOffer({
+          tokenAAmount: new anchor.BN(400000000000),
+          takerMerkleProof: [],
+          expectedTokenAAmount: new anchor.BN(396000000000),
+          maxTokenBAmount: new anchor.BN(399999999999),
+          maxTakerFeeBps: 30
+        })
+        .preInstructions([
+          anchor.web3.ComputeBudgetProgram.setComputeUnitLimit({ units: 400_000 })
+        ])
+        .accounts(takeOffer1Accounts(taker))
+        .signers([takerKeypair, fundingAccountKeypair])
+        .rpc(),
+      "SlippageExceeded"
+    );
+  });
+
+  it("take offer rejects a max taker fee below the taker fee", async () => {
+    await expectAnchorError(
+      program.methods
+        .takeOffer({
+          tokenAAmount: new anchor.BN(400000000000),
+          takerMerkleProof: [],
+          expectedTokenAAmount: new anchor.BN(396000000000),
+          maxTokenBAmount: new anchor.BN(400000000000),
+          maxTakerFeeBps: 29
+        })
+        .preInstructions([
+          anchor.web3.ComputeBudgetProgram.setComputeUnitLimit({ units: 400_000 })
+        ])
+        .accounts(takeOffer1Accounts(taker))
+        .signers([takerKeypair, fundingAccountKeypair])
+        .rpc(),
+      "SlippageExceeded"
+    );
+  });
+
+  it("take offer rejects a taker missing from the allowlist", async () => {
+    await expectAnchorError(
+      program.methods
+        .takeOffer({
+          tokenAAmount: new anchor.BN(400000000000),
+          takerMerkleProof: [],
+          expectedTokenAAmount: new anchor.BN(0),
+          maxTokenBAmount: new anchor.BN(400000000000),
+          maxTakerFeeBps: 30
+        })
+        .preInstructions([
+          anchor.web3.ComputeBudgetProgram.setComputeUnitLimit({ units: 400_000 })
+        ])
+        .accounts({ ...takeOffer1Accounts(outsider), takerTokenBAccount: null })
+        .signers([outsiderKeypair, fundingAccountKeypair])
+        .rpc(),
+      "InvalidMerkleProof"
+    );
+  });
+
+  it("take offer rejects takes while paused", async () => {
+    await program.methods
+      .setPause({ makePaused: false, takePaused: true })
+      .accounts({
+        escrowState: escrowState,
+        escrowManager: newManager
+      })
+      .signers([newManagerKeypair])
+      .rpc();
+
+    await expectAnchorError(
+      program.methods
+        .takeOffer({
+          tokenAAmount: new anchor.BN(400000000000),
+          takerMerkleProof: [],
+          expectedTokenAAmount: new anchor.BN(396000000000),
+          maxTokenBAmount: new anchor.BN(400000000000),
+          maxTakerFeeBps: 30
+        })
+        .preInstructions([
+          anchor.web3.ComputeBudgetProgram.setComputeUnitLimit({ units: 400_000 })
+        ])
+        .accounts(takeOffer1Accounts(taker))
+        .signers([takerKeypair, fundingAccountKeypair])
+        .rpc(),
+      "TakePaused"
+    );
+
+    const tx = await program.methods
+      .setPause({ makePaused: false, takePaused: false })
+      .accounts({
+        escrowState: escrowState,
+        escrowManager: newManager
+      })
+      .signers([newManagerKeypair])
+      .rpc();
+    console.log("Your transaction signature", tx);
+  });
+
+  it("take offer partially", async () => {
+    const tx = await program.methods
+      .takeOffer({
+        tokenAAmount: new anchor.BN(400000000000),
+        takerMerkleProof: [],
+        expectedTokenAAmount: new anchor.BN(396000000000),
+        maxTokenBAmount: new anchor.BN(400000000000),
+        maxTakerFeeBps: 30
+      })
+      .preInstructions([
+        anchor.web3.ComputeBudgetProgram.setComputeUnitLimit({ units: 400_000 })
+      ])
+      .accounts({
+        escrowState: escrowState,
+        escrowAccount: escrowAccount1,
+        tokenAMintAccount: tokenMintA,
+        tokenBMintAccount: tokenMintB,
+        tokenAMintConfig: mintConfigAddress(tokenMintA),
+        tokenBMintConfig: mintConfigAddress(tokenMintB),
+        pairFeeConfig: pairFeeConfigAddress(tokenMintA, tokenMintB),
+        makerTraderStats: traderStatsAddress(maker),
+        takerTraderStats: traderStatsAddress(taker),
+        makerTokenBAccount: makerTokenBAccount,
+        takerTokenAAccount: takerTokenAAccount,
+        takerTokenBAccount: takerTokenBAccount,
+        escrowTokenAFeeAccount: escrowTokenAFeeAccount,
+        escrowTokenBFeeAccount: escrowTokenBFeeAccount,
+        escrowTokenAVaultAccount: escrowTokenAVaultAccount1,
+        referrer: null,
+        referrerRegistration: null,
+        referrerTokenAAccount: null,
+        referrerTokenBAccount: null,
+        maker: maker,
+        funder: fundingAccount,
+        taker: taker,
+        fundingAccount: fundingAccount,
+        tokenAProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        tokenBProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId
+      })
+      .signers([takerKeypair, fundingAccountKeypair])
+      .rpc({ skipPreflight: true });
+    console.log("Your transaction signature", tx);
+  });
+
+  it("take offer remaining", async () => {
+    const tx = await program.methods
+      .takeOffer({
+        tokenAAmount: new anchor.BN(600000000000),
+        takerMerkleProof: [],
+        expectedTokenAAmount: new anchor.BN(594000000000),
+        maxTokenBAmount: new anchor.BN(600000000000),
+        maxTakerFeeBps: 30
+      })
+      .preInstructions([
+        anchor.web3.ComputeBudgetProgram.setComputeUnitLimit({ units: 400_000 })
+      ])
+      .accounts({
+        escrowState: escrowState,
+        escrowAccount: escrowAccount1,
+        tokenAMintAccount: tokenMintA,
+        tokenBMintAccount: tokenMintB,
+        tokenAMintConfig: mintConfigAddress(tokenMintA),
+        tokenBMintConfig: mintConfigAddress(tokenMintB),
+        pairFeeConfig: pairFeeConfigAddress(tokenMintA, tokenMintB),
+        makerTraderStats: traderStatsAddress(maker),
+        takerTraderStats: traderStatsAddress(taker),
+        makerTokenBAccount: makerTokenBAccount,
+        takerTokenAAccount: takerTokenAAccount,
+        takerTokenBAccount: takerTokenBAccount,
+        escrowTokenAFeeAccount: escrowTokenAFeeAccount,
+        escrowTokenBFeeAccount: escrowTokenBFeeAccount,
+        escrowTokenAVaultAccount: escrowTokenAVaultAccount1,
+        referrer: referrer,
+        referrerRegistration: referrerRegistration,
+        referrerTokenAAccount: referrerTokenAAccount,
+        referrerTokenBAccount: referrerTokenBAccount,
+        maker: maker,
+        funder: fundingAccount,
+        taker: taker,
+        fundingAccount: fundingAccount,
+        tokenAProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        tokenBProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId
+      })
+      .signers([takerKeypair, fundingAccountKeypair])
+      .rpc({ skipPreflight: true });
+    console.log("Your transaction signature", tx);
+
+    for (const referrerTokenAccount of [
+      referrerTokenAAccount,
+      referrerTokenBAccount
+    ]) {
+      const balance = await program.provider.connection.getTokenAccountBalance(
+        referrerTokenAccount
+      );
+      expect(Number(balance.value.amount)).to.be.greaterThan(0);
+    }
+
+    const makerTraderStats = await program.account.traderStats.fetch(
+      traderStatsAddress(maker)
+    );
+    const takerTraderStats = await program.account.traderStats.fetch(
+      traderStatsAddress(taker)
+    );
+    expect(makerTraderStats.makerVolume.toNumber()).to.be.greaterThan(0);
+    expect(takerTraderStats.takerVolume.toNumber()).to.be.greaterThan(0);
+  });
+
+  it("make offer", async () => {
+    const tx = await program.methods
+      .makeOffer(makeOffer2Args)
+      .accounts({
+        escrowState: escrowState,
+        escrowAccount: escrowAccount2,
+        tokenAMintAccount: tokenMintA,
+        tokenBMintAccount: tokenMintB,
+        tokenAMintConfig: mintConfigAddress(tokenMintA),
+        tokenBMintConfig: mintConfigAddress(tokenMintB),
+        makerTokenAAccount: makerTokenAAccount,
+        escrowTokenAVaultAccount: escrowTokenAVaultAccount2,
+        maker: maker,
+        fundingAccount: fundingAccount,
+        tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId
+      })
+      .signers([makerKeypair, fundingAccountKeypair])
+      .rpc();
+    console.log("Your transaction signature", tx);
+  });
+
+  it("update offer", async () => {
+    const tx = await program.methods
+      .updateOffer({
+        tokenBWantedAmount: new anchor.BN(1200000000000),
+        tokenATopUpAmount: new anchor.BN(0),
+        tokenAWithdrawAmount: new anchor.BN(100000000000)
+      })
+      .accounts({
+        escrowState: escrowState,
+        escrowAccount: escrowAccount2,
+        tokenAMintAccount: tokenMintA,
+        makerTokenAAccount: makerTokenAAccount,
+        escrowTokenAVaultAccount: escrowTokenAVaultAccount2,
+        maker: maker,
+        fundingAccount: fundingAccount,
+        tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId
+      })
+      .signers([makerKeypair, fundingAccountKeypair])
+      .rpc();
+    console.log("Your transaction signature", tx);
+  });
+
+  it("cancel offer", async () => {
+    const tx = await program.methods
+      .cancelOffer()
+      .accounts({
+        escrowAccount: escrowAccount2,
+        tokenAMintAccount: tokenMintA,
+        makerTokenAAccount: makerTokenAAccount,
+        escrowTokenAVaultAccount: escrowTokenAVaultAccount2,
+        maker: maker,
+        fundingAccount: fundingAccount,
+        tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId
+      })
+      .signers([makerKeypair, fundingAccountKeypair])
+      .rpc();
+    console.log("Your transaction signature", tx);
+  });
+
+  it("reclaim expired offer", async () => {
+    const expiresAt = Math.floor(Date.now() / 1000) + 2;
+
+    await program.methods
+      .makeOffer({
+        id: new anchor.BN(2),
+        tokenAOfferedAmount: new anchor.BN(1000000000),
+        tokenBWantedAmount: new anchor.BN(1000000000),
+        expiresAt: new anchor.BN(expiresAt),
+        allowedTaker: null,
+        takerMerkleRoot: null,
+        kind: { fixed: {} }
+      })
+      .accounts({
+        escrowState: escrowState,
+        escrowAccount: escrowAccount3,
+        tokenAMintAccount: tokenMintA,
+        tokenBMintAccount: tokenMintB,
+        tokenAMintConfig: mintConfigAddress(tokenMintA),
+        tokenBMintConfig: mintConfigAddress(tokenMintB),
+        makerTokenAAccount: makerTokenAAccount,
+        escrowTokenAVaultAccount: escrowTokenAVaultAccount3,
+        maker: maker,
+        fundingAccount: fundingAccount,
+        tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId
+      })
+      .signers([makerKeypair, fundingAccountKeypair])
+      .rpc();
+
+    await new Promise((resolve) => setTimeout(resolve, 4000));
+
+    const tx = await program.methods
+      .reclaimExpiredOffer()
+      .accounts({
+        escrowAccount: escrowAccount3,
+        tokenAMintAccount: tokenMintA,
+        makerTokenAAccount: makerTokenAAccount,
+        escrowTokenAVaultAccount: escrowTokenAVaultAccount3,
+        maker: maker,
+        funder: fundingAccount,
+        fundingAccount: fundingAccount,
+        tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId
+      })
+      .signers([fundingAccountKeypair])
+      .rpc();
+    console.log("Your transaction signature", tx);
+  });
+
+  it("make and take private token-2022 offer", async () => {
+    await program.methods
+      .makeOffer({
+        id: new anchor.BN(3),
+        tokenAOfferedAmount: new anchor.BN(1000000000),
+        tokenBWantedAmount: new anchor.BN(1000000000),
+        expiresAt: null,
+        allowedTaker: taker,
+        takerMerkleRoot: null,
+        kind: { fixed: {} }
+      })
+      .accounts({
+        escrowState: escrowState,
+        escrowAccount: escrowAccount4,
+        tokenAMintAccount: tokenMint2022,
+        tokenBMintAccount: tokenMintB,
+        tokenAMintConfig: mintConfigAddress(tokenMint2022),
+        tokenBMintConfig: mintConfigAddress(tokenMintB),
+        makerTokenAAccount: makerToken2022Account,
+        escrowTokenAVaultAccount: escrowToken2022VaultAccount4,
+        maker: maker,
+        fundingAccount: fundingAccount,
+        tokenProgram: token.TOKEN_2022_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId
+      })
+      .signers([makerKeypair, fundingAccountKeypair])
+      .rpc();
+
+    await expectAnchorError(
+      program.methods
+        .takeOffer({
+          tokenAAmount: new anchor.BN(1000000000),
+          takerMerkleProof: [],
+          expectedTokenAAmount: new anchor.BN(0),
+          maxTokenBAmount: new anchor.BN(1000000000),
+          maxTakerFeeBps: 200
+        })
+        .preInstructions([
+          anchor.web3.ComputeBudgetProgram.setComputeUnitLimit({ units: 400_000 })
+        ])
+        .accounts({
+          escrowState: escrowState,
+          escrowAccount: escrowAccount4,
+          tokenAMintAccount: tokenMint2022,
+          tokenBMintAccount: tokenMintB,
+          tokenAMintConfig: mintConfigAddress(tokenMint2022),
+          tokenBMintConfig: mintConfigAddress(tokenMintB),
+          pairFeeConfig: pairFeeConfigAddress(tokenMint2022, tokenMintB),
+          makerTraderStats: traderStatsAddress(maker),
+          takerTraderStats: traderStatsAddress(outsider),
+          makerTokenBAccount: makerTokenBAccount,
+          takerTokenAAccount: token.getAssociatedTokenAddressSync(
+            tokenMint2022,
+            outsider,
+            false,
+            token.TOKEN_2022_PROGRAM_ID
+          ),
+          takerTokenBAccount: null,
+          escrowTokenAFeeAccount: escrowToken2022FeeAccount,
+          escrowTokenBFeeAccount: escrowTokenBFeeAccount,
+          escrowTokenAVaultAccount: escrowToken2022VaultAccount4,
+          referrer: null,
+          referrerRegistration: null,
+          referrerTokenAAccount: null,
+          referrerTokenBAccount: null,
+          maker: maker,
+          funder: fundingAccount,
+          taker: outsider,
+          fundingAccount: fundingAccount,
+          tokenAProgram: token.TOKEN_2022_PROGRAM_ID,
+          tokenBProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+          associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+          systemProgram: anchor.web3.SystemProgram.programId
+        })
+        .signers([outsiderKeypair, fundingAccountKeypair])
+        .rpc(),
+      "TakerNotAllowed"
+    );
+
+    const tx = await program.methods
+      .takeOffer({
+        tokenAAmount: new anchor.BN(1000000000),
+        takerMerkleProof: [],
+        expectedTokenAAmount: new anchor.BN(950000000),
+        maxTokenBAmount: new anchor.BN(1000000000),
+        maxTakerFeeBps: 200
+      })
+      .preInstructions([
+        anchor.web3.ComputeBudgetProgram.setComputeUnitLimit({ units: 400_000 })
+      ])
+      .accounts({
+        escrowState: escrowState,
+        escrowAccount: escrowAccount4,
+        tokenAMintAccount: tokenMint2022,
+        tokenBMintAccount: tokenMintB,
+        tokenAMintConfig: mintConfigAddress(tokenMint2022),
+        tokenBMintConfig: mintConfigAddress(tokenMintB),
+        pairFeeConfig: pairFeeConfigAddress(tokenMint2022, tokenMintB),
+        makerTraderStats: traderStatsAddress(maker),
+        takerTraderStats: traderStatsAddress(taker),
+        makerTokenBAccount: makerTokenBAccount,
+        takerTokenAAccount: takerToken2022Account,
+        takerTokenBAccount: takerTokenBAccount,
+        escrowTokenAFeeAccount: escrowToken2022FeeAccount,
+        escrowTokenBFeeAccount: escrowTokenBFeeAccount,
+        escrowTokenAVaultAccount: escrowToken2022VaultAccount4,
+        referrer: null,
+        referrerRegistration: null,
+        referrerTokenAAccount: null,
+        referrerTokenBAccount: null,
+        maker: maker,
+        funder: fundingAccount,
+        taker: taker,
+        fundingAccount: fundingAccount,
+        tokenAProgram: token.TOKEN_2022_PROGRAM_ID,
+        tokenBProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId
+      })
+      .signers([takerKeypair, fundingAccountKeypair])
+      .rpc({ skipPreflight: true });
+    console.log("Your transaction signature", tx);
+  });
+
+  it("make, take and cancel transfer fee token-2022 offers", async () => {
+    // Token-2022 mint withholding 1% of every transfer
+    const transferFeeMintKeypair = anchor.web3.Keypair.generate();
+    const transferFeeMint = transferFeeMintKeypair.publicKey;
+    const mintLen = token.getMintLen([token.ExtensionType.TransferFeeConfig]);
+
+    await anchor.web3.sendAndConfirmTransaction(
+      program.provider.connection,
+      new anchor.web3.Transaction().add(
+        anchor.web3.SystemProgram.createAccount({
+          fromPubkey: fundingAccount,
+          newAccountPubkey: transferFeeMint,
+          space: mintLen,
+          lamports:
+            await program.provider.connection.getMinimumBalanceForRentExemption(
+              mintLen
+            ),
+          programId: token.TOKEN_2022_PROGRAM_ID
+        }),
+        token.createInitializeTransferFeeConfigInstruction(
+          transferFeeMint,
+          fundingAccount,
+          fundingAccount,
+          100,
+          BigInt(1000000000),
+          token.TOKEN_2022_PROGRAM_ID
+        ),
+        token.createInitializeMintInstruction(
+          transferFeeMint,
+          6,
+          fundingAccount,
+          null,
+          token.TOKEN_2022_PROGRAM_ID
+        )
+      ),
+      [fundingAccountKeypair, transferFeeMintKeypair]
+    );
+
+    const associatedToken2022Address = (
+      owner: anchor.web3.PublicKey,
+      allowOwnerOffCurve = false
+    ) =>
+      token.getAssociatedTokenAddressSync(
+        transferFeeMint,
+        owner,
+        allowOwnerOffCurve,
+        token.TOKEN_2022_PROGRAM_ID
+      );
+    const tokenBalance = async (tokenAccount: anchor.web3.PublicKey) =>
+      Number(
+        (await program.provider.connection.getTokenAccountBalance(tokenAccount))
+          .value.amount
+      );
+    const offerAddress = (id: number) =>
+      anchor.web3.PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("offer"),
+          maker.toBuffer(),
+          new anchor.BN(id).toArrayLike(Buffer, "le", 8)
+        ],
+        program.programId
+      )[0];
+
+    const makerTransferFeeAccount = await token.createAssociatedTokenAccount(
+      program.provider.connection,
+      fundingAccountKeypair,
+      transferFeeMint,
+      maker,
+      undefined,
+      token.TOKEN_2022_PROGRAM_ID
+    );
+    await token.mintTo(
+      program.provider.connection,
+      fundingAccountKeypair,
+      transferFeeMint,
+      makerTransferFeeAccount,
+      fundingAccountKeypair,
+      1000000000,
+      [],
+      undefined,
+      token.TOKEN_2022_PROGRAM_ID
+    );
+
+    const makeTransferFeeOffer = (id: number) =>
+      program.methods
+        .makeOffer({
+          id: new anchor.BN(id),
+          tokenAOfferedAmount: new anchor.BN(100000000),
+          tokenBWantedAmount: new anchor.BN(100000000),
+          expiresAt: null,
+          allowedTaker: null,
+          takerMerkleRoot: null,
+          kind: { fixed: {} }
+        })
+        .accounts({
+          escrowState: escrowState,
+          escrowAccount: offerAddress(id),
+          tokenAMintAccount: transferFeeMint,
+          tokenBMintAccount: tokenMintB,
+          tokenAMintConfig: mintConfigAddress(transferFeeMint),
+          tokenBMintConfig: mintConfigAddress(tokenMintB),
+          makerTokenAAccount: makerTransferFeeAccount,
+          escrowTokenAVaultAccount: associatedToken2022Address(
+            offerAddress(id),
+            true
+          ),
+          maker: maker,
+          fundingAccount: fundingAccount,
+          tokenProgram: token.TOKEN_2022_PROGRAM_ID,
+          associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+          systemProgram: anchor.web3.SystemProgram.programId
+        })
+        .signers([makerKeypair, fundingAccountKeypair])
+        .rpc();
+
+    const takeTransferFeeOffer = (tokenAAmount: number) =>
+      program.methods
+        .takeOffer({
+          tokenAAmount: new anchor.BN(tokenAAmount),
+          takerMerkleProof: [],
+          expectedTokenAAmount: new anchor.BN(0),
+          maxTokenBAmount: new anchor.BN(100000000),
+          maxTakerFeeBps: 200
+        })
+        .preInstructions([
+          anchor.web3.ComputeBudgetProgram.setComputeUnitLimit({
+            units: 400_000
+          })
+        ])
+        .accounts({
+          escrowState: escrowState,
+          escrowAccount: offerAddress(9),
+          tokenAMintAccount: transferFeeMint,
+          tokenBMintAccount: tokenMintB,
+          tokenAMintConfig: mintConfigAddress(transferFeeMint),
+          tokenBMintConfig: mintConfigAddress(tokenMintB),
+          pairFeeConfig: pairFeeConfigAddress(transferFeeMint, tokenMintB),
+          makerTraderStats: traderStatsAddress(maker),
+          takerTraderStats: traderStatsAddress(taker),
+          makerTokenBAccount: makerTokenBAccount,
+          takerTokenAAccount: associatedToken2022Address(taker),
+          takerTokenBAccount: takerTokenBAccount,
+          escrowTokenAFeeAccount: associatedToken2022Address(escrowState, true),
+          escrowTokenBFeeAccount: escrowTokenBFeeAccount,
+          escrowTokenAVaultAccount: associatedToken2022Address(
+            offerAddress(9),
+            true
+          ),
+          referrer: null,
+          referrerRegistration: null,
+          referrerTokenAAccount: null,
+          referrerTokenBAccount: null,
+          maker: maker,
+          funder: fundingAccount,
+          taker: taker,
+          fundingAccount: fundingAccount,
+          tokenAProgram: token.TOKEN_2022_PROGRAM_ID,
+          tokenBProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+          associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+          systemProgram: anchor.web3.SystemProgram.programId
+        })
+        .signers([takerKeypair, fundingAccountKeypair])
+        .rpc();
+
+    // The offer records the 99% which reached the vault
+    await makeTransferFeeOffer(9);
+
+    const offer = await program.account.offer.fetch(offerAddress(9));
+    expect(offer.tokenAOfferedAmount.toNumber()).to.equal(99000000);
+    expect(
+      await tokenBalance(associatedToken2022Address(offerAddress(9), true))
+    ).to.equal(99000000);
+
+    await takeTransferFeeOffer(49500000);
+
+    const partlyFilledOffer = await program.account.offer.fetch(
+      offerAddress(9)
+    );
+    expect(partlyFilledOffer.tokenAFilledAmount.toNumber()).to.equal(49500000);
+    expect(partlyFilledOffer.tokenBFilledAmount.toNumber()).to.equal(50000000);
+
+    // The final take settles the rest of the net amount and closes the vault,
+    // which only succeeds once its withheld fees are harvested
+    const tx = await takeTransferFeeOffer(49500000);
+    console.log("Your transaction signature", tx);
+
+    expect(
+      await program.provider.connection.getAccountInfo(
+        associatedToken2022Address(offerAddress(9), true)
+      )
+    ).to.be.null;
+    expect(await program.provider.connection.getAccountInfo(offerAddress(9)))
+      .to.be.null;
+
+    // Cancelling returns the vault to the maker, less the fee withheld on the way back
+    await makeTransferFeeOffer(10);
+
+    const makerAmountBeforeCancel = await tokenBalance(makerTransferFeeAccount);
+
+    await program.methods
+      .cancelOffer()
+      .accounts({
+        escrowAccount: offerAddress(10),
+        tokenAMintAccount: transferFeeMint,
+        makerTokenAAccount: makerTransferFeeAccount,
+        escrowTokenAVaultAccount: associatedToken2022Address(
+          offerAddress(10),
+          true
+        ),
+        maker: maker,
+        fundingAccount: fundingAccount,
+        tokenProgram: token.TOKEN_2022_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId
+      })
+      .signers([makerKeypair, fundingAccountKeypair])
+      .rpc();
+
+    expect(
+      (await tokenBalance(makerTransferFeeAccount)) - makerAmountBeforeCancel
+    ).to.equal(98010000);
+    expect(
+      await program.provider.connection.getAccountInfo(
+        associatedToken2022Address(offerAddress(10), true)
+      )
+    ).to.be.null;
+    expect(await program.provider.connection.getAccountInfo(offerAddress(10)))
+      .to.be.null;
+  });
+
+  it("make and take native SOL offer", async () => {
+    const tx0 = await program.provider.connection.requestAirdrop(
+      maker,
+      anchor.web3.LAMPORTS_PER_SOL * 10
+    );
+    await program.provider.connection.confirmTransaction(tx0);
+
+    await program.methods
+      .makeOffer({
+        id: new anchor.BN(4),
+        tokenAOfferedAmount: new anchor.BN(anchor.web3.LAMPORTS_PER_SOL),
+        tokenBWantedAmount: new anchor.BN(1000000000),
+        expiresAt: null,
+        allowedTaker: null,
+        takerMerkleRoot: null,
+        kind: { fixed: {} }
+      })
+      .accounts({
+        escrowState: escrowState,
+        escrowAccount: escrowAccount5,
+        tokenAMintAccount: token.NATIVE_MINT,
+        tokenBMintAccount: tokenMintB,
+        tokenAMintConfig: mintConfigAddress(token.NATIVE_MINT),
+        tokenBMintConfig: mintConfigAddress(tokenMintB),
+        makerTokenAAccount: null,
+        escrowTokenAVaultAccount: escrowNativeVaultAccount5,
+        maker: maker,
+        fundingAccount: fundingAccount,
+        tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId
+      })
+      .signers([makerKeypair, fundingAccountKeypair])
+      .rpc();
+
+    const tx = await program.methods
+      .takeOffer({
+        tokenAAmount: new anchor.BN(anchor.web3.LAMPORTS_PER_SOL),
+        takerMerkleProof: [],
+        expectedTokenAAmount: new anchor.BN(0.97 * anchor.web3.LAMPORTS_PER_SOL),
+        maxTokenBAmount: new anchor.BN(1000000000),
+        maxTakerFeeBps: 200
+      })
+      .preInstructions([
+        anchor.web3.ComputeBudgetProgram.setComputeUnitLimit({ units: 400_000 })
+      ])
+      .accounts({
+        escrowState: escrowState,
+        escrowAccount: escrowAccount5,
+        tokenAMintAccount: token.NATIVE_MINT,
+        tokenBMintAccount: tokenMintB,
+        tokenAMintConfig: mintConfigAddress(token.NATIVE_MINT),
+        tokenBMintConfig: mintConfigAddress(tokenMintB),
+        pairFeeConfig: pairFeeConfigAddress(token.NATIVE_MINT, tokenMintB),
+        makerTraderStats: traderStatsAddress(maker),
+        takerTraderStats: traderStatsAddress(taker),
+        makerTokenBAccount: makerTokenBAccount,
+        takerTokenAAccount: takerNativeAccount,
+        takerTokenBAccount: takerTokenBAccount,
+        escrowTokenAFeeAccount: escrowNativeFeeAccount,
+        escrowTokenBFeeAccount: escrowTokenBFeeAccount,
+        escrowTokenAVaultAccount: escrowNativeVaultAccount5,
+        referrer: null,
+        referrerRegistration: null,
+        referrerTokenAAccount: null,
+        referrerTokenBAccount: null,
+        maker: maker,
+        funder: fundingAccount,
+        taker: taker,
+        fundingAccount: fundingAccount,
+        tokenAProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        tokenBProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId
+      })
+      .signers([takerKeypair, fundingAccountKeypair])
+      .rpc({ skipPreflight: true });
+    console.log("Your transaction signature", tx);
+  });
+
+  it("make and take dutch auction offer", async () => {
+    const startTime = Math.floor(Date.now() / 1000);
+
+    await program.methods
+      .makeOffer({
+        id: new anchor.BN(5),
+        tokenAOfferedAmount: new anchor.BN(1000000000),
+        tokenBWantedAmount: new anchor.BN(2000000000),
+        expiresAt: null,
+        allowedTaker: null,
+        takerMerkleRoot: null,
+        kind: {
+          dutchAuction: {
+            endTokenBWantedAmount: new anchor.BN(1000000000),
+            startTime: new anchor.BN(startTime),
+            endTime: new anchor.BN(startTime + 3600)
+          }
+        }
+      })
+      .accounts({
+        escrowState: escrowState,
+        escrowAccount: escrowAccount6,
+        tokenAMintAccount: tokenMintA,
+        tokenBMintAccount: tokenMintB,
+        tokenAMintConfig: mintConfigAddress(tokenMintA),
+        tokenBMintConfig: mintConfigAddress(tokenMintB),
+        makerTokenAAccount: makerTokenAAccount,
+        escrowTokenAVaultAccount: escrowTokenAVaultAccount6,
+        maker: maker,
+        fundingAccount: fundingAccount,
+        tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId
+      })
+      .signers([makerKeypair, fundingAccountKeypair])
+      .rpc();
+
+    const tx = await program.methods
+      .takeOffer({
+        tokenAAmount: new anchor.BN(1000000000),
+        takerMerkleProof: [],
+        expectedTokenAAmount: new anchor.BN(990000000),
+        maxTokenBAmount: new anchor.BN(2000000000),
+        maxTakerFeeBps: 30
+      })
+      .preInstructions([
+        anchor.web3.ComputeBudgetProgram.setComputeUnitLimit({ units: 400_000 })
+      ])
+      .accounts({
+        escrowState: escrowState,
+        escrowAccount: escrowAccount6,
+        tokenAMintAccount: tokenMintA,
+        tokenBMintAccount: tokenMintB,
+        tokenAMintConfig: mintConfigAddress(tokenMintA),
+        tokenBMintConfig: mintConfigAddress(tokenMintB),
+        pairFeeConfig: pairFeeConfigAddress(tokenMintA, tokenMintB),
+        makerTraderStats: traderStatsAddress(maker),
+        takerTraderStats: traderStatsAddress(taker),
+        makerTokenBAccount: makerTokenBAccount,
+        takerTokenAAccount: takerTokenAAccount,
+        takerTokenBAccount: takerTokenBAccount,
+        escrowTokenAFeeAccount: escrowTokenAFeeAccount,
+        escrowTokenBFeeAccount: escrowTokenBFeeAccount,
+        escrowTokenAVaultAccount: escrowTokenAVaultAccount6,
+        referrer: null,
+        referrerRegistration: null,
+        referrerTokenAAccount: null,
+        referrerTokenBAccount: null,
+        maker: maker,
+        funder: fundingAccount,
+        taker: taker,
+        fundingAccount: fundingAccount,
+        tokenAProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        tokenBProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId
+      })
+      .signers([takerKeypair, fundingAccountKeypair])
+      .rpc({ skipPreflight: true });
+    console.log("Your transaction signature", tx);
+  });
+
+  it("make and take bid offer", async () => {
+    const escrowAccount7 = anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("offer"),
+        maker.toBuffer(),
+        new anchor.BN(6).toArrayLike(Buffer, "le", 8)
+      ],
+      program.programId
+    )[0];
+    const escrowTokenBVaultAccount7 = anchor.utils.token.associatedAddress({
+      mint: tokenMintB,
+      owner: escrowAccount7
+    });
+
+    await program.methods
+      .makeBid({
+        id: new anchor.BN(6),
+        tokenAWantedAmount: new anchor.BN(100000000),
+        tokenBOfferedAmount: new anchor.BN(200000000),
+        expiresAt: null,
+        allowedTaker: null,
+        takerMerkleRoot: null
+      })
+      .accounts({
+        escrowState: escrowState,
+        escrowAccount: escrowAccount7,
+        tokenAMintAccount: tokenMintA,
+        tokenBMintAccount: tokenMintB,
+        tokenAMintConfig: mintConfigAddress(tokenMintA),
+        tokenBMintConfig: mintConfigAddress(tokenMintB),
+        makerTokenBAccount: makerTokenBAccount,
+        escrowTokenBVaultAccount: escrowTokenBVaultAccount7,
+        maker: maker,
+        fundingAccount: fundingAccount,
+        tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId
+      })
+      .signers([makerKeypair, fundingAccountKeypair])
+      .rpc();
+
+    const tx = await program.methods
+      .takeBid({
+        tokenAAmount: new anchor.BN(100000000),
+        takerMerkleProof: [],
+        minTokenBAmount: new anchor.BN(190000000),
+        maxTakerFeeBps: 30
+      })
+      .preInstructions([
+        anchor.web3.ComputeBudgetProgram.setComputeUnitLimit({ units: 400_000 })
+      ])
+      .accounts({
+        escrowState: escrowState,
+        escrowAccount: escrowAccount7,
+        tokenAMintAccount: tokenMintA,
+        tokenBMintAccount: tokenMintB,
+        tokenAMintConfig: mintConfigAddress(tokenMintA),
+        tokenBMintConfig: mintConfigAddress(tokenMintB),
+        pairFeeConfig: pairFeeConfigAddress(tokenMintA, tokenMintB),
+        makerTraderStats: traderStatsAddress(maker),
+        takerTraderStats: traderStatsAddress(taker),
+        makerTokenAAccount: makerTokenAAccount,
+        takerTokenAAccount: takerTokenAAccount,
+        takerTokenBAccount: takerTokenBAccount,
+        escrowTokenAFeeAccount: escrowTokenAFeeAccount,
+        escrowTokenBFeeAccount: escrowTokenBFeeAccount,
+        escrowTokenBVaultAccount: escrowTokenBVaultAccount7,
+        maker: maker,
+        funder: fundingAccount,
+        taker: taker,
+        fundingAccount: fundingAccount,
+        tokenAProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        tokenBProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId
+      })
+      .signers([takerKeypair, fundingAccountKeypair])
+      .rpc({ skipPreflight: true });
+    console.log("Your transaction signature", tx);
+  });
+
+  it("reclaim expired bid", async () => {
+    const escrowAccount8 = anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("offer"),
+        maker.toBuffer(),
+        new anchor.BN(8).toArrayLike(Buffer, "le", 8)
+      ],
+      program.programId
+    )[0];
+    const escrowTokenBVaultAccount8 = anchor.utils.token.associatedAddress({
+      mint: tokenMintB,
+      owner: escrowAccount8
+    });
+    const expiresAt = Math.floor(Date.now() / 1000) + 2;
+
+    await program.methods
+      .makeBid({
+        id: new anchor.BN(8),
+        tokenAWantedAmount: new anchor.BN(100000000),
+        tokenBOfferedAmount: new anchor.BN(200000000),
+        expiresAt: new anchor.BN(expiresAt),
+        allowedTaker: null,
+        takerMerkleRoot: null
+      })
+      .accounts({
+        escrowState: escrowState,
+        escrowAccount: escrowAccount8,
+        tokenAMintAccount: tokenMintA,
+        tokenBMintAccount: tokenMintB,
+        tokenAMintConfig: mintConfigAddress(tokenMintA),
+        tokenBMintConfig: mintConfigAddress(tokenMintB),
+        makerTokenBAccount: makerTokenBAccount,
+        escrowTokenBVaultAccount: escrowTokenBVaultAccount8,
+        maker: maker,
+        fundingAccount: fundingAccount,
+        tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId
+      })
+      .signers([makerKeypair, fundingAccountKeypair])
+      .rpc();
+
+    await new Promise((resolve) => setTimeout(resolve, 4000));
+
+    const tx = await program.methods
+      .reclaimExpiredBid()
+      .accounts({
+        escrowAccount: escrowAccount8,
+        tokenBMintAccount: tokenMintB,
+        makerTokenBAccount: makerTokenBAccount,
+        escrowTokenBVaultAccount: escrowTokenBVaultAccount8,
+        maker: maker,
+        funder: fundingAccount,
+        fundingAccount: fundingAccount,
+        tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId
+      })
+      .signers([fundingAccountKeypair])
+      .rpc();
+    console.log("Your transaction signature", tx);
+  });
+
+  it("match crossing ask and bid", async () => {
+    const askOffer = anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("offer"),
+        maker.toBuffer(),
+        new anchor.BN(7).toArrayLike(Buffer, "le", 8)
+      ],
+      program.programId
+    )[0];
+    const bidOffer = anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("offer"),
+        taker.toBuffer(),
+        new anchor.BN(0).toArrayLike(Buffer, "le", 8)
+      ],
+      program.programId
+    )[0];
+    const askTokenAVaultAccount = anchor.utils.token.associatedAddress({
+      mint: tokenMintA,
+      owner: askOffer
+    });
+    const bidTokenBVaultAccount = anchor.utils.token.associatedAddress({
+      mint: tokenMintB,
+      owner: bidOffer
+    });
+
+    await program.methods
+      .makeOffer({
+        id: new anchor.BN(7),
+        tokenAOfferedAmount: new anchor.BN(100000000),
+        tokenBWantedAmount: new anchor.BN(150000000),
+        expiresAt: null,
+        allowedTaker: null,
+        takerMerkleRoot: null,
+        kind: { fixed: {} }
+      })
+      .accounts({
+        escrowState: escrowState,
+        escrowAccount: askOffer,
+        tokenAMintAccount: tokenMintA,
+        tokenBMintAccount: tokenMintB,
+        tokenAMintConfig: mintConfigAddress(tokenMintA),
+        tokenBMintConfig: mintConfigAddress(tokenMintB),
+        makerTokenAAccount: makerTokenAAccount,
+        escrowTokenAVaultAccount: askTokenAVaultAccount,
+        maker: maker,
+        fundingAccount: fundingAccount,
+        tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId
+      })
+      .signers([makerKeypair, fundingAccountKeypair])
+      .rpc();
+
+    await program.methods
+      .makeBid({
+        id: new anchor.BN(0),
+        tokenAWantedAmount: new anchor.BN(100000000),
+        tokenBOfferedAmount: new anchor.BN(200000000),
+        expiresAt: null,
+        allowedTaker: null,
+        takerMerkleRoot: null
+      })
+      .accounts({
+        escrowState: escrowState,
+        escrowAccount: bidOffer,
+        tokenAMintAccount: tokenMintA,
+        tokenBMintAccount: tokenMintB,
+        tokenAMintConfig: mintConfigAddress(tokenMintA),
+        tokenBMintConfig: mintConfigAddress(tokenMintB),
+        makerTokenBAccount: takerTokenBAccount,
+        escrowTokenBVaultAccount: bidTokenBVaultAccount,
+        maker: taker,
+        fundingAccount: fundingAccount,
+        tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId
+      })
+      .signers([takerKeypair, fundingAccountKeypair])
+      .rpc();
+
+    const tx = await program.methods
+      .matchOffers()
+      .preInstructions([
+        anchor.web3.ComputeBudgetProgram.setComputeUnitLimit({ units: 400_000 })
+      ])
+      .accounts({
+        escrowState: escrowState,
+        askOffer: askOffer,
+        bidOffer: bidOffer,
+        tokenAMintAccount: tokenMintA,
+        tokenBMintAccount: tokenMintB,
+        tokenAMintConfig: mintConfigAddress(tokenMintA),
+        tokenBMintConfig: mintConfigAddress(tokenMintB),
+        pairFeeConfig: pairFeeConfigAddress(tokenMintA, tokenMintB),
+        askMakerTraderStats: traderStatsAddress(maker),
+        bidMakerTraderStats: traderStatsAddress(taker),
+        askMakerTokenBAccount: makerTokenBAccount,
+        bidMakerTokenAAccount: takerTokenAAccount,
+        // The ask maker cranks its own match, so it is also paid the surplus
+        crankerTokenBAccount: makerTokenBAccount,
+        escrowTokenAFeeAccount: escrowTokenAFeeAccount,
+        escrowTokenBFeeAccount: escrowTokenBFeeAccount,
+        askTokenAVaultAccount: askTokenAVaultAccount,
+        bidTokenBVaultAccount: bidTokenBVaultAccount,
+        askMaker: maker,
+        bidMaker: taker,
+        askFunder: fundingAccount,
+        bidFunder: fundingAccount,
+        cranker: maker,
+        tokenAProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        tokenBProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId
+      })
+      .signers([makerKeypair])
+      .rpc({ skipPreflight: true });
+    console.log("Your transaction signature", tx);
+  });
+
+  it("make and take basket offer", async () => {
+    const basketOffer = anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("basket_offer"),
+        maker.toBuffer(),
+        new anchor.BN(0).toArrayLike(Buffer, "le", 8)
+      ],
+      program.programId
+    )[0];
+    const basketTokenAVaultAccount = anchor.utils.token.associatedAddress({
+      mint: tokenMintA,
+      owner: basketOffer
+    });
+    const basketTokenBVaultAccount = anchor.utils.token.associatedAddress({
+      mint: tokenMintB,
+      owner: basketOffer
+    });
+    const account = (pubkey: anchor.web3.PublicKey, isWritable: boolean) => ({
+      pubkey,
+      isWritable,
+      isSigner: false
+    });
+
+    await program.methods
+      .makeBasketOffer({
+        id: new anchor.BN(0),
+        offered: [
+          { mint: tokenMintA, amount: new anchor.BN(100000000) },
+          { mint: tokenMintB, amount: new anchor.BN(10000000) }
+        ],
+        wanted: [
+          { mint: tokenMintB, amount: new anchor.BN(150000000) },
+          { mint: tokenMint2022, amount: new anchor.BN(100000000) }
+        ],
+        expiresAt: null,
+        allowedTaker: null
+      })
+      .accounts({
+        escrowState: escrowState,
+        basketOffer: basketOffer,
+        maker: maker,
+        fundingAccount: fundingAccount,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId
+      })
+      .remainingAccounts([
+        account(tokenMintA, false),
+        account(anchor.utils.token.TOKEN_PROGRAM_ID, false),
+        account(mintConfigAddress(tokenMintA), false),
+        account(makerTokenAAccount, true),
+        account(basketTokenAVaultAccount, true),
+        account(tokenMintB, false),
+        account(anchor.utils.token.TOKEN_PROGRAM_ID, false),
+        account(mintConfigAddress(tokenMintB), false),
+        account(makerTokenBAccount, true),
+        account(basketTokenBVaultAccount, true),
+        account(mintConfigAddress(tokenMintB), false),
+        account(mintConfigAddress(tokenMint2022), false)
+      ])
+      .signers([makerKeypair, fundingAccountKeypair])
+      .rpc();
+
+    const tx = await program.methods
+      .takeBasketOffer({ maxTakerFeeBps: 200 })
+      .preInstructions([
+        anchor.web3.ComputeBudgetProgram.setComputeUnitLimit({ units: 400_000 })
+      ])
+      .accounts({
+        escrowState: escrowState,
+        basketOffer: basketOffer,
+        maker: maker,
+        funder: fundingAccount,
+        taker: taker,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId
+      })
+      .remainingAccounts([
+        account(tokenMintA, false),
+        account(anchor.utils.token.TOKEN_PROGRAM_ID, false),
+        account(mintConfigAddress(tokenMintA), false),
+        account(basketTokenAVaultAccount, true),
+        account(takerTokenAAccount, true),
+        account(makerTokenAAccount, true),
+        account(escrowTokenAFeeAccount, true),
+        account(tokenMintB, false),
+        account(anchor.utils.token.TOKEN_PROGRAM_ID, false),
+        account(mintConfigAddress(tokenMintB), false),
+        account(basketTokenBVaultAccount, true),
+        account(takerTokenBAccount, true),
+        account(makerTokenBAccount, true),
+        account(escrowTokenBFeeAccount, true),
+        account(tokenMintB, false),
+        account(anchor.utils.token.TOKEN_PROGRAM_ID, false),
+        account(mintConfigAddress(tokenMintB), false),
+        account(takerTokenBAccount, true),
+        account(makerTokenBAccount, true),
+        account(escrowTokenBFeeAccount, true),
+        account(tokenMint2022, false),
+        account(token.TOKEN_2022_PROGRAM_ID, false),
+        account(mintConfigAddress(tokenMint2022), false),
+        account(takerToken2022Account, true),
+        account(makerToken2022Account, true),
+        account(escrowToken2022FeeAccount, true)
+      ])
+      .signers([takerKeypair])
+      .rpc({ skipPreflight: true });
+    console.log("Your transaction signature", tx);
+  });
+
+  it("reclaim expired basket offer", async () => {
+    const basketOffer = anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("basket_offer"),
+        maker.toBuffer(),
+        new anchor.BN(1).toArrayLike(Buffer, "le", 8)
+      ],
+      program.programId
+    )[0];
+    const basketTokenAVaultAccount = anchor.utils.token.associatedAddress({
+      mint: tokenMintA,
+      owner: basketOffer
+    });
+    const account = (pubkey: anchor.web3.PublicKey, isWritable: boolean) => ({
+      pubkey,
+      isWritable,
+      isSigner: false
+    });
+    const expiresAt = Math.floor(Date.now() / 1000) + 2;
+
+    await program.methods
+      .makeBasketOffer({
+        id: new anchor.BN(1),
+        offered: [{ mint: tokenMintA, amount: new anchor.BN(100000000) }],
+        wanted: [{ mint: tokenMintB, amount: new anchor.BN(150000000) }],
+        expiresAt: new anchor.BN(expiresAt),
+        allowedTaker: null
+      })
+      .accounts({
+        escrowState: escrowState,
+        basketOffer: basketOffer,
+        maker: maker,
+        fundingAccount: fundingAccount,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId
+      })
+      .remainingAccounts([
+        account(tokenMintA, false),
+        account(anchor.utils.token.TOKEN_PROGRAM_ID, false),
+        account(mintConfigAddress(tokenMintA), false),
+        account(makerTokenAAccount, true),
+        account(basketTokenAVaultAccount, true),
+        account(mintConfigAddress(tokenMintB), false)
+      ])
+      .signers([makerKeypair, fundingAccountKeypair])
+      .rpc();
+
+    const reclaim = () =>
+      program.methods
+        .reclaimExpiredBasketOffer()
+        .accounts({
+          basketOffer: basketOffer,
+          maker: maker,
+          funder: fundingAccount,
+          fundingAccount: fundingAccount,
+          associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+          systemProgram: anchor.web3.SystemProgram.programId
+        })
+        .remainingAccounts([
+          account(tokenMintA, false),
+          account(anchor.utils.token.TOKEN_PROGRAM_ID, false),
+          account(basketTokenAVaultAccount, true),
+          account(makerTokenAAccount, true)
+        ])
+        .signers([fundingAccountKeypair])
+        .rpc();
+
+    await expectAnchorError(reclaim(), "OfferNotExpired");
+
+    await new Promise((resolve) => setTimeout(resolve, 4000));
+
+    const makerTokenAAmountBefore = Number(
+      (
+        await program.provider.connection.getTokenAccountBalance(
+          makerTokenAAccount
+        )
+      ).value.amount
+    );
+
+    const tx = await reclaim();
+    console.log("Your transaction signature", tx);
+
+    const makerTokenAAmountAfter = Number(
+      (
+        await program.provider.connection.getTokenAccountBalance(
+          makerTokenAAccount
+        )
+      ).value.amount
+    );
+    expect(makerTokenAAmountAfter - makerTokenAAmountBefore).to.equal(
+      100000000
+    );
+    expect(
+      await program.provider.connection.getAccountInfo(basketTokenAVaultAccount)
+    ).to.be.null;
+    expect(await program.provider.connection.getAccountInfo(basketOffer)).to.be
+      .null;
+  });
+
+  it("create, bid on and settle auction", async () => {
+    const auction = anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("auction"),
+        maker.toBuffer(),
+        new anchor.BN(0).toArrayLike(Buffer, "le", 8)
+      ],
+      program.programId
+    )[0];
+    const auctionTokenAVaultAccount = anchor.utils.token.associatedAddress({
+      mint: tokenMintA,
+      owner: auction
+    });
+    const auctionTokenBBidVaultAccount = anchor.utils.token.associatedAddress({
+      mint: tokenMintB,
+      owner: auction
+    });
+    const endTime = Math.floor(Date.now() / 1000) + 2;
+
+    await program.methods
+      .createAuction({
+        id: new anchor.BN(0),
+        tokenAAmount: new anchor.BN(1000000000),
+        minBidAmount: new anchor.BN(500000000),
+        minBidIncrement: new anchor.BN(10000000),
+        endTime: new anchor.BN(endTime)
+      })
+      .accounts({
+        escrowState: escrowState,
+        auction: auction,
+        tokenAMintAccount: tokenMintA,
+        tokenBMintAccount: tokenMintB,
+        tokenAMintConfig: mintConfigAddress(tokenMintA),
+        tokenBMintConfig: mintConfigAddress(tokenMintB),
+        makerTokenAAccount: makerTokenAAccount,
+        auctionTokenAVaultAccount: auctionTokenAVaultAccount,
+        maker: maker,
+        fundingAccount: fundingAccount,
+        tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId
+      })
+      .signers([makerKeypair, fundingAccountKeypair])
+      .rpc();
+
+    await program.methods
+      .placeBid({ bidAmount: new anchor.BN(600000000) })
+      .accounts({
+        escrowState: escrowState,
+        auction: auction,
+        tokenBMintAccount: tokenMintB,
+        bidderTokenBAccount: takerTokenBAccount,
+        auctionTokenBBidVaultAccount: auctionTokenBBidVaultAccount,
+        previousBidRefund: null,
+        bidder: taker,
+        fundingAccount: fundingAccount,
+        tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId
+      })
+      .signers([takerKeypair, fundingAccountKeypair])
+      .rpc();
+
+    await new Promise((resolve) => setTimeout(resolve, 4000));
+
+    const tx = await program.methods
+      .settleAuction()
+      .preInstructions([
+        anchor.web3.ComputeBudgetProgram.setComputeUnitLimit({ units: 400_000 })
+      ])
+      .accounts({
+        escrowState: escrowState,
+        auction: auction,
+        tokenAMintAccount: tokenMintA,
+        tokenBMintAccount: tokenMintB,
+        tokenBMintConfig: mintConfigAddress(tokenMintB),
+        pairFeeConfig: pairFeeConfigAddress(tokenMintA, tokenMintB),
+        auctionTokenAVaultAccount: auctionTokenAVaultAccount,
+        auctionTokenBBidVaultAccount: auctionTokenBBidVaultAccount,
+        winner: taker,
+        winnerTokenAAccount: takerTokenAAccount,
+        makerTokenAAccount: null,
+        makerTokenBAccount: makerTokenBAccount,
+        escrowTokenBFeeAccount: escrowTokenBFeeAccount,
+        maker: maker,
+        funder: fundingAccount,
+        fundingAccount: fundingAccount,
+        tokenAProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        tokenBProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId
+      })
+      .signers([fundingAccountKeypair])
+      .rpc({ skipPreflight: true });
+    console.log("Your transaction signature", tx);
+  });
+
+  it("outbid, settle and withdraw bid refunds of auction", async () => {
+    const auction = anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("auction"),
+        maker.toBuffer(),
+        new anchor.BN(1).toArrayLike(Buffer, "le", 8)
+      ],
+      program.programId
+    )[0];
+    const auctionTokenAVaultAccount = anchor.utils.token.associatedAddress({
+      mint: tokenMintA,
+      owner: auction
+    });
+    const auctionTokenBBidVaultAccount = anchor.utils.token.associatedAddress({
+      mint: tokenMintB,
+      owner: auction
+    });
+    const bidRefundAddress = (bidder: anchor.web3.PublicKey) =>
+      anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("bid_refund"), auction.toBuffer(), bidder.toBuffer()],
+        program.programId
+      )[0];
+    const tokenBalance = async (tokenAccount: anchor.web3.PublicKey) =>
+      Number(
+        (await program.provider.connection.getTokenAccountBalance(tokenAccount))
+          .value.amount
+      );
+
+    // Second bidder competing with the taker
+    const bidderKeypair = anchor.web3.Keypair.generate();
+    const bidder = bidderKeypair.publicKey;
+
+    await anchor.web3.sendAndConfirmTransaction(
+      program.provider.connection,
+      new anchor.web3.Transaction().add(
+        anchor.web3.SystemProgram.transfer({
+          fromPubkey: fundingAccount,
+          toPubkey: bidder,
+          lamports: anchor.web3.LAMPORTS_PER_SOL / 10
+        })
+      ),
+      [fundingAccountKeypair]
+    );
+    const bidderTokenBAccount = await token.createAssociatedTokenAccount(
+      program.provider.connection,
+      fundingAccountKeypair,
+      tokenMintB,
+      bidder
+    );
+    await token.mintTo(
+      program.provider.connection,
+      fundingAccountKeypair,
+      tokenMintB,
+      bidderTokenBAccount,
+      fundingAccountKeypair,
+      2000000000
+    );
+
+    const endTime = Math.floor(Date.now() / 1000) + 8;
+
+    await program.methods
+      .createAuction({
+        id: new anchor.BN(1),
+        tokenAAmount: new anchor.BN(1000000000),
+        minBidAmount: new anchor.BN(500000000),
+        minBidIncrement: new anchor.BN(10000000),
+        endTime: new anchor.BN(endTime)
+      })
+      .accounts({
+        escrowState: escrowState,
+        auction: auction,
+        tokenAMintAccount: tokenMintA,
+        tokenBMintAccount: tokenMintB,
+        tokenAMintConfig: mintConfigAddress(tokenMintA),
+        tokenBMintConfig: mintConfigAddress(tokenMintB),
+        makerTokenAAccount: makerTokenAAccount,
+        auctionTokenAVaultAccount: auctionTokenAVaultAccount,
+        maker: maker,
+        fundingAccount: fundingAccount,
+        tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId
+      })
+      .signers([makerKeypair, fundingAccountKeypair])
+      .rpc();
+
+    const placeBid = (
+      bidAmount: number,
+      bidderSigner: anchor.web3.Keypair,
+      bidderTokenB: anchor.web3.PublicKey,
+      previousBidder: anchor.web3.PublicKey | null
+    ) =>
+      program.methods
+        .placeBid({ bidAmount: new anchor.BN(bidAmount) })
+        .accounts({
+          escrowState: escrowState,
+          auction: auction,
+          tokenBMintAccount: tokenMintB,
+          bidderTokenBAccount: bidderTokenB,
+          auctionTokenBBidVaultAccount: auctionTokenBBidVaultAccount,
+          previousBidRefund: previousBidder
+            ? bidRefundAddress(previousBidder)
+            : null,
+          bidder: bidderSigner.publicKey,
+          fundingAccount: fundingAccount,
+          tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+          associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+          systemProgram: anchor.web3.SystemProgram.programId
+        })
+        .signers([bidderSigner, fundingAccountKeypair])
+        .rpc();
+
+    const withdrawBidRefund = (
+      bidderSigner: anchor.web3.Keypair,
+      bidderTokenB: anchor.web3.PublicKey
+    ) =>
+      program.methods
+        .withdrawBidRefund()
+        .accounts({
+          auction: auction,
+          bidRefund: bidRefundAddress(bidderSigner.publicKey),
+          tokenBMintAccount: tokenMintB,
+          bidderTokenBAccount: bidderTokenB,
+          auctionTokenBBidVaultAccount: auctionTokenBBidVaultAccount,
+          refundFunder: fundingAccount,
+          funder: fundingAccount,
+          bidder: bidderSigner.publicKey,
+          tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+          associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+          systemProgram: anchor.web3.SystemProgram.programId
+        })
+        .signers([bidderSigner])
+        .rpc();
+
+    await placeBid(600000000, bidderKeypair, bidderTokenBAccount, null);
+
+    // The outbid amount stays in the bid vault until the bidder withdraws it
+    await placeBid(700000000, takerKeypair, takerTokenBAccount, bidder);
+
+    const bidRefund = await program.account.bidRefund.fetch(
+      bidRefundAddress(bidder)
+    );
+    expect(bidRefund.amount.toNumber()).to.equal(600000000);
+    expect(await tokenBalance(auctionTokenBBidVaultAccount)).to.equal(
+      1300000000
+    );
+
+    const bidderAmountBeforeWithdrawal = await tokenBalance(bidderTokenBAccount);
+
+    await withdrawBidRefund(bidderKeypair, bidderTokenBAccount);
+
+    expect(
+      (await tokenBalance(bidderTokenBAccount)) - bidderAmountBeforeWithdrawal
+    ).to.equal(600000000);
+    expect(
+      await program.provider.connection.getAccountInfo(bidRefundAddress(bidder))
+    ).to.be.null;
+    const openAuction = await program.account.auction.fetch(auction);
+    expect(openAuction.pendingRefundAmount.toNumber()).to.equal(0);
+
+    // The taker is outbid in turn and leaves its refund pending over settlement
+    await placeBid(800000000, bidderKeypair, bidderTokenBAccount, taker);
+
+    await new Promise((resolve) =>
+      setTimeout(resolve, (endTime + 2) * 1000 - Date.now())
+    );
+
+    const makerAmountBeforeSettlement = await tokenBalance(makerTokenBAccount);
+
+    await program.methods
+      .settleAuction()
+      .preInstructions([
+        anchor.web3.ComputeBudgetProgram.setComputeUnitLimit({ units: 400_000 })
+      ])
+      .accounts({
+        escrowState: escrowState,
+        auction: auction,
+        tokenAMintAccount: tokenMintA,
+        tokenBMintAccount: tokenMintB,
+        tokenBMintConfig: mintConfigAddress(tokenMintB),
+        pairFeeConfig: pairFeeConfigAddress(tokenMintA, tokenMintB),
+        auctionTokenAVaultAccount: auctionTokenAVaultAccount,
+        auctionTokenBBidVaultAccount: auctionTokenBBidVaultAccount,
+        winner: bidder,
+        winnerTokenAAccount: anchor.utils.token.associatedAddress({
+          mint: tokenMintA,
+          owner: bidder
+        }),
+        makerTokenAAccount: null,
+        makerTokenBAccount: makerTokenBAccount,
+        escrowTokenBFeeAccount: escrowTokenBFeeAccount,
+        maker: maker,
+        funder: fundingAccount,
+        fundingAccount: fundingAccount,
+        tokenAProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        tokenBProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId
+      })
+      .signers([fundingAccountKeypair])
+      .rpc();
+
+    // Settlement pays the maker but keeps the pending refund, the vault and the auction
+    const settledAuction = await program.account.auction.fetch(auction);
+    expect(settledAuction.settled).to.be.true;
+    expect(settledAuction.pendingRefundAmount.toNumber()).to.equal(700000000);
+    expect(await tokenBalance(auctionTokenBBidVaultAccount)).to.equal(
+      700000000
+    );
+    expect(
+      (await tokenBalance(makerTokenBAccount)) - makerAmountBeforeSettlement
+    ).to.be.above(0);
+
+    // The last withdrawal closes the bid vault and the auction
+    const takerAmountBeforeWithdrawal = await tokenBalance(takerTokenBAccount);
+
+    const tx = await withdrawBidRefund(takerKeypair, takerTokenBAccount);
+    console.log("Your transaction signature", tx);
+
+    expect(
+      (await tokenBalance(takerTokenBAccount)) - takerAmountBeforeWithdrawal
+    ).to.equal(700000000);
+    expect(
+      await program.provider.connection.getAccountInfo(bidRefundAddress(taker))
+    ).to.be.null;
+    expect(
+      await program.provider.connection.getAccountInfo(
+        auctionTokenBBidVaultAccount
+      )
+    ).to.be.null;
+    expect(await program.provider.connection.getAccountInfo(auction)).to.be
+      .null;
+  });
+
+  it("close pair fee config", async () => {
+    const tx = await program.methods
+      .closePairFeeConfig()
+      .accounts({
+        escrowState: escrowState,
+        escrowManager: newManager,
+        pairFeeConfig: pairFeeConfigAddress(tokenMintA, tokenMintB)
+      })
+      .signers([newManagerKeypair])
+      .rpc();
+    console.log("Your transaction signature", tx);
+  });
+
+  it("escrow state collect fee A", async () => {
+    const escrowFeeAccount = anchor.utils.token.associatedAddress({
+      mint: tokenMintA,
+      owner: escrowState
+    });
+    const managerTokenAccount = anchor.utils.token.associatedAddress({
+      mint: tokenMintA,
+      owner: newManager
+    });
+
+    // Add your test here.
+    const tx = await program.methods
+      .collectFee({ shouldCloseFeeAccount: false })
+      .accounts({
+        escrowState: escrowState,
+        escrowManager: newManager,
+        feeSplitConfig: feeSplitConfig,
+        tokenMintAccount: tokenMintA,
+        escrowFeeAccount: escrowFeeAccount,
+        managerTokenAccount: managerTokenAccount,
+        tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId
+      })
+      .signers([newManagerKeypair])
+      .rpc({ skipPreflight: true });
+    console.log("Your transaction signature", tx);
+  });
+
+  it("escrow state collect fee B", async () => {
+    const escrowFeeAccount = anchor.utils.token.associatedAddress({
+      mint: tokenMintB,
+      owner: escrowState
+    });
+    const managerTokenAccount = anchor.utils.token.associatedAddress({
+      mint: tokenMintB,
+      owner: newManager
+    });
+    const tx = await program.methods
+      .collectFee({ shouldCloseFeeAccount: true })
+      .accounts({
+        escrowState: escrowState,
+        escrowManager: newManager,
+        feeSplitConfig: feeSplitConfig,
+        tokenMintAccount: tokenMintB,
+        escrowFeeAccount: escrowFeeAccount,
+        managerTokenAccount: managerTokenAccount,
+        tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId
+      })
+      .signers([newManagerKeypair])
+      .rpc({ skipPreflight: true });
+    console.log("Your transaction signature", tx);
+  });
+
+  it("set fee split config and distribute fees", async () => {
+    const treasury = anchor.web3.Keypair.generate().publicKey;
+    const operations = anchor.web3.Keypair.generate().publicKey;
+
+    await program.methods
+      .setFeeSplitConfig({
+        recipients: [
+          { wallet: treasury, weightBps: 7_000 },
+          { wallet: operations, weightBps: 3_000 }
+        ]
+      })
+      .accounts({
+        escrowState: escrowState,
+        escrowManager: newManager,
+        feeSplitConfig: feeSplitConfig,
+        systemProgram: anchor.web3.SystemProgram.programId
+      })
+      .signers([newManagerKeypair])
+      .rpc();
+
+    // The recipients have no token A accounts yet, distribute_fees creates them
+    const recipientAccounts = [];
+    for (const recipient of [treasury, operations]) {
+      recipientAccounts.push(
+        { pubkey: recipient, isWritable: false, isSigner: false },
+        {
+          pubkey: anchor.utils.token.associatedAddress({
+            mint: tokenMintA,
+            owner: recipient
+          }),
+          isWritable: true,
+          isSigner: false
+        }
+      );
+    }
+
+    const tx = await program.methods
+      .distributeFees()
+      .accounts({
+        escrowState: escrowState,
+        feeSplitConfig: feeSplitConfig,
+        tokenMintAccount: tokenMintA,
+        escrowFeeAccount: escrowTokenAFeeAccount,
+        payer: fundingAccount,
+        tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId
+      })
+      .remainingAccounts(recipientAccounts)
+      .signers([fundingAccountKeypair])
+      .rpc();
+    console.log("Your transaction signature", tx);
+  });
+
+  it("collect fee rejects collecting around the fee split", async () => {
+    await expectAnchorError(
+      program.methods
+        .collectFee({ shouldCloseFeeAccount: false })
+        .accounts({
+          escrowState: escrowState,
+          escrowManager: newManager,
+          feeSplitConfig: feeSplitConfig,
+          tokenMintAccount: tokenMintA,
+          escrowFeeAccount: escrowTokenAFeeAccount,
+          managerTokenAccount: anchor.utils.token.associatedAddress({
+            mint: tokenMintA,
+            owner: newManager
+          }),
+          tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+          associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+          systemProgram: anchor.web3.SystemProgram.programId
+        })
+        .signers([newManagerKeypair])
+        .rpc(),
+      "FeeSplitConfigured"
+    );
+  });
+
+  it("close fee split config and collect fee again", async () => {
+    await program.methods
+      .closeFeeSplitConfig()
+      .accounts({
+        escrowState: escrowState,
+        escrowManager: newManager,
+        feeSplitConfig: feeSplitConfig
+      })
+      .signers([newManagerKeypair])
+      .rpc();
+
+    const tx = await program.methods
+      .collectFee({ shouldCloseFeeAccount: false })
+      .accounts({
+        escrowState: escrowState,
+        escrowManager: newManager,
+        feeSplitConfig: feeSplitConfig,
+        tokenMintAccount: tokenMintA,
+        escrowFeeAccount: escrowTokenAFeeAccount,
+        managerTokenAccount: anchor.utils.token.associatedAddress({
+          mint: tokenMintA,
+          owner: newManager
+        }),
+        tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId
+      })
+      .signers([newManagerKeypair])
+      .rpc();
     console.log("Your transaction signature", tx);
   });
 });